@@ -1,12 +1,15 @@
-use criterion::{criterion_group, criterion_main, Criterion};
-use rustchess::{movegen, game};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rustchess::{movegen, game, magic, perft};
+
+#[path = "../tests/common/rays.rs"]
+mod rays;
 
 pub fn criterion_benchmark(c: &mut Criterion) {
     let game = match game::GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1") {
       Ok(x) => x,
       Err(_) => {
         println!("could not create board");
-        return ();
+        return ;
       },
     };
 
@@ -15,8 +18,35 @@ pub fn criterion_benchmark(c: &mut Criterion) {
        movegen::MoveGen::pseudo_legal(&game);  
       });
     });
+
+    c.bench_function("start position legal move generation", |b| {
+      b.iter(|| {
+        movegen::MoveGen::legal(&game)
+      });
+    });
+
+    // the old ray walk against the magic lookup on a middlegame occupancy
+    let occupied = 0xbd5f_a4c3_1846_e7b9u64;
+
+    c.bench_function("sliding attacks with rays", |b| {
+      b.iter(|| {
+        (0..64).fold(0u64, |acc, sq| acc ^ rays::rook_rays(sq, black_box(occupied)) ^ rays::bishop_rays(sq, black_box(occupied)))
+      });
+    });
+
+    c.bench_function("sliding attacks with magics", |b| {
+      b.iter(|| {
+        (0..64).fold(0u64, |acc, sq| acc ^ magic::rook_attacks(sq, black_box(occupied)) ^ magic::bishop_attacks(sq, black_box(occupied)))
+      });
+    });
+
+    c.bench_function("perft 3 kiwipete", |b| {
+      b.iter(|| {
+        let mut game = game::Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        perft::perft(&mut game, 3)
+      });
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
 criterion_main!(benches);
-
@@ -32,13 +32,19 @@ pub struct BitBoard {
   pub bitboard: u64,
 }
 
+impl Default for BitBoard {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 impl BitBoard {
   pub fn new() -> Self {
     BitBoard {bitboard: 0u64 }
   }
   
   pub fn flip_bit(&mut self, shift: i32) -> Result<(), &'static str>{
-    if shift > 64 || shift < 0 {
+    if !(0..=64).contains(&shift) {
       return Err("Idx is not well defined");
     }
 
@@ -48,7 +54,7 @@ impl BitBoard {
   }
 
   pub fn get_bit(&self, shift: i32) -> Result<bool, &'static str> {
-    if shift > 64 || shift < 0 {
+    if !(0..=64).contains(&shift) {
       return Err("Idx is not well defined");
     }
 
@@ -94,7 +100,7 @@ impl Board {
   pub fn get_piece(&self, shift: i32) -> Option<(Player, Pieces)> {
     for player in Player::iter() {
       for piece in Pieces::iter() {
-        if self.bb_board[player.clone() as usize][piece.clone() as usize].get_bit(shift) == Ok(true) {
+        if self.bb_board[player as usize][piece as usize].get_bit(shift) == Ok(true) {
           return Some((player, piece));
         } 
       }
@@ -104,7 +110,7 @@ impl Board {
 
 
   pub fn get_pieceboard(&self, player: Player, piece: Pieces) -> BitBoard {
-    self.bb_board[player as usize][piece as usize].clone()
+    self.bb_board[player as usize][piece as usize]
   }
   
   pub fn get_freesq_mask(&self) -> u64 {
@@ -112,7 +118,7 @@ impl Board {
 
     for player in Player::iter() {
       for piece in Pieces::iter() {
-        ret |= self.bb_board[player.clone() as usize][piece.clone() as usize].bitboard;
+        ret |= self.bb_board[player as usize][piece as usize].bitboard;
       }
     }
 
@@ -124,7 +130,7 @@ impl Board {
   }
 
   pub fn get_player_mask(&self, player: Player) -> u64 {
-    let ret: u64 = self.bb_board[player.clone() as usize].iter().fold(0u64, |acc, &bb| bb.bitboard | acc);
+    let ret: u64 = self.bb_board[player as usize].iter().fold(0u64, |acc, &bb| bb.bitboard | acc);
     ret
   }
 
@@ -141,16 +147,13 @@ impl Board {
   pub fn from_fen(fen: &str) -> Result<Self, &'static str> {
     let mut fields = fen.split(" ");
 
-    let mut board_fen = match fields.next() {
-      Some(x) => x,
-      None => "8/8/8/8/8/8/8/8",
-    }.chars();
+    let board_fen = fields.next().unwrap_or("8/8/8/8/8/8/8/8").chars();
 
     let mut ret = Board {bb_board: [[BitBoard::new(); 6]; 2]};
 
     let mut shift = 63;
 
-    while let Some(fen_sym) = board_fen.next() {
+    for fen_sym in board_fen {
       let res = match fen_sym {
         'r' => ret.flip_piece(Player::Black, Pieces::Rook, shift),
         'b' => ret.flip_piece(Player::Black, Pieces::Bishop, shift),
@@ -190,6 +193,53 @@ impl Board {
     Ok(ret)
   }
 
+  pub fn to_fen(self) -> String {
+    let mut ret = String::new();
+    let mut empty = 0;
+
+    for shift in (0..64).rev() {
+      let sym = match self.get_piece(shift) {
+        Some((Player::Black, Pieces::Rook)) => 'r',
+        Some((Player::Black, Pieces::Bishop)) => 'b',
+        Some((Player::Black, Pieces::Pawn)) => 'p',
+        Some((Player::Black, Pieces::Queen)) => 'q',
+        Some((Player::Black, Pieces::King)) => 'k',
+        Some((Player::Black, Pieces::Knight)) => 'n',
+        Some((Player::White, Pieces::Rook)) => 'R',
+        Some((Player::White, Pieces::Bishop)) => 'B',
+        Some((Player::White, Pieces::Pawn)) => 'P',
+        Some((Player::White, Pieces::Queen)) => 'Q',
+        Some((Player::White, Pieces::King)) => 'K',
+        Some((Player::White, Pieces::Knight)) => 'N',
+        None => {
+          empty += 1;
+          ' '
+        },
+      };
+
+      if sym != ' ' {
+        if empty > 0 {
+          ret.push_str(&empty.to_string());
+          empty = 0;
+        }
+        ret.push(sym);
+      }
+
+      // end of rank
+      if shift & 7 == 0 {
+        if empty > 0 {
+          ret.push_str(&empty.to_string());
+          empty = 0;
+        }
+        if shift > 0 {
+          ret.push('/');
+        }
+      }
+    }
+
+    ret
+  }
+
   #[allow(dead_code)]
   pub fn print_board(&self) {
     print!("8 ");
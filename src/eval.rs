@@ -0,0 +1,385 @@
+use crate::game::{GameState};
+use crate::board::{Board, Player, Pieces};
+use crate::score::{Score};
+use crate::pawns::{self, PawnEntry, PawnTable, adjacent_files, file_mask, forward_ranks};
+use crate::movegen::{MoveGen};
+use crate::params::{EvalParams, DEFAULT_PARAMS};
+
+use std::fmt;
+
+// game phase of every piece, the phase is 24 with all pieces on the board and 0 with only pawns left
+const PHASE_WEIGHT: [i32; 6] = [0, 2, 1, 1, 4, 0];
+pub const MAX_PHASE: i32 = 24;
+
+// the usual number of safe squares, mobility counts above or below it. indexed like Pieces
+const MOBILITY_BASE: [i32; 6] = [0, 7, 4, 6, 13, 0];
+
+const PIECES: [Pieces; 6] = [Pieces::Pawn, Pieces::Rook, Pieces::Knight, Pieces::Bishop, Pieces::Queen, Pieces::King];
+
+// index into the tables for a square of the absolute board (shift 63 is a8, shift 0 is h1),
+// black reads the tables upside down
+#[inline]
+fn pst_index(player: Player, shift: u32) -> usize {
+  match player {
+    Player::White => (shift ^ 63) as usize,
+    Player::Black => (shift ^ 7) as usize,
+  }
+}
+
+#[inline]
+fn piece_attacks(piece: Pieces, bit: u64, free_mask: u64) -> u64 {
+  match piece {
+    Pieces::Knight => MoveGen::get_knight_attacks(bit),
+    Pieces::Bishop => MoveGen::get_bishop_attacks(bit, free_mask),
+    Pieces::Rook => MoveGen::get_rook_attacks(bit, free_mask),
+    Pieces::Queen => MoveGen::get_queen_attacks(bit, free_mask),
+    Pieces::King => MoveGen::get_king_attacks(bit),
+    Pieces::Pawn => 0,
+  }
+}
+
+// the square as seen by `player`, black's pieces are mirrored to white's side of the board
+#[inline]
+fn relative_square(player: Player, shift: u32) -> u32 {
+  match player {
+    Player::White => shift,
+    Player::Black => shift ^ 56,
+  }
+}
+
+// squares the pieces of `player` attack that are neither their own nor covered by an enemy pawn
+pub fn mobility(board: &Board, player: Player, params: &EvalParams) -> (i32, i32) {
+  let free_mask = board.get_freesq_mask();
+  let opp_pawns = board.get_pieceboard(player.other(), Pieces::Pawn).bitboard;
+  let safe = !board.get_player_mask(player) & !pawns::pawn_attacks(opp_pawns, player.other());
+  let mut mg = 0;
+  let mut eg = 0;
+
+  for piece in [Pieces::Rook, Pieces::Knight, Pieces::Bishop, Pieces::Queen] {
+    let mut pieces = board.get_pieceboard(player, piece).bitboard;
+
+    while pieces != 0 {
+      let bit = 1u64 << pieces.trailing_zeros();
+      pieces ^= bit;
+
+      let n = (piece_attacks(piece, bit, free_mask) & safe).count_ones() as i32 - MOBILITY_BASE[piece as usize];
+      mg += n * params.mobility[piece as usize].0;
+      eg += n * params.mobility[piece as usize].1;
+    }
+  }
+
+  (mg, eg)
+}
+
+// bishop pair, rooks on open files and the 7th rank, knight outposts and trapped rooks and bishops
+pub fn piece_activity(board: &Board, player: Player, params: &EvalParams) -> (i32, i32) {
+  let own_pawns = board.get_pieceboard(player, Pieces::Pawn).bitboard;
+  let opp_pawns = board.get_pieceboard(player.other(), Pieces::Pawn).bitboard;
+  let king = board.get_pieceboard(player, Pieces::King).bitboard;
+  let opp_king = board.get_pieceboard(player.other(), Pieces::King).bitboard;
+  let free_mask = board.get_freesq_mask();
+  let mut score = (0, 0);
+  let mut add = |w: (i32, i32)| {
+    score.0 += w.0;
+    score.1 += w.1;
+  };
+
+  if board.count_pieces(player, Pieces::Bishop) >= 2 {
+    add(params.bishop_pair);
+  }
+
+  let mut rooks = board.get_pieceboard(player, Pieces::Rook).bitboard;
+  while rooks != 0 {
+    let shift = rooks.trailing_zeros();
+    let sq = relative_square(player, shift);
+    rooks &= rooks - 1;
+
+    if (own_pawns | opp_pawns) & file_mask(shift) == 0 {
+      add(params.rook_open_file);
+    } else if own_pawns & file_mask(shift) == 0 {
+      add(params.rook_semi_open_file);
+    }
+
+    let seventh = 0xffu64 << (shift & !7);
+    if sq / 8 == 6 && (opp_pawns & seventh != 0 || opp_king != 0 && relative_square(player, opp_king.trailing_zeros()) / 8 == 7) {
+      add(params.rook_on_seventh);
+    }
+
+    // a king that stepped aside without castling keeps the rook in the corner, columns count from the h-file
+    if king != 0 && sq / 8 <= 1 && (MoveGen::get_rook_attacks(1u64 << shift, free_mask) & free_mask).count_ones() <= 3 {
+      let ksq = relative_square(player, king.trailing_zeros());
+      let (rook_col, king_col) = (sq & 7, ksq & 7);
+
+      if ksq / 8 == 0 && (((1..=2).contains(&king_col) && rook_col < king_col) || ((4..=6).contains(&king_col) && rook_col > king_col)) {
+        add(params.trapped_rook);
+      }
+    }
+  }
+
+  let own_pawn_attacks = pawns::pawn_attacks(own_pawns, player);
+  let mut knights = board.get_pieceboard(player, Pieces::Knight).bitboard;
+  while knights != 0 {
+    let shift = knights.trailing_zeros();
+    knights &= knights - 1;
+
+    let rank = relative_square(player, shift) / 8;
+    if (3..=5).contains(&rank) && own_pawn_attacks & (1u64 << shift) != 0 &&
+       opp_pawns & adjacent_files(shift) & forward_ranks(player, shift) == 0 {
+      add(params.knight_outpost);
+    }
+  }
+
+  // a7 is shut in by b6 and h7 by g6, squares as seen by the player
+  let mut bishops = board.get_pieceboard(player, Pieces::Bishop).bitboard;
+  while bishops != 0 {
+    let shift = bishops.trailing_zeros();
+    bishops &= bishops - 1;
+
+    let blocker = match relative_square(player, shift) {
+      55 => 46,
+      48 => 41,
+      _ => continue,
+    };
+    if opp_pawns & (1u64 << relative_square(player, blocker)) != 0 {
+      add(params.trapped_bishop);
+    }
+  }
+
+  score
+}
+
+// ranks to the closest of `pawns` in front of `sq` on the same file, as seen by `player`
+fn pawn_distance(player: Player, sq: u32, pawns: u64) -> Option<u32> {
+  let pawns = pawns & file_mask(sq) & forward_ranks(player, sq);
+  if pawns == 0 {
+    return None;
+  }
+
+  let closest = match player {
+    Player::White => pawns.trailing_zeros(),
+    Player::Black => 63 - pawns.leading_zeros(),
+  };
+  Some((closest / 8).abs_diff(sq / 8))
+}
+
+// pawn shield, pawn storm, open files and pieces attacking the squares around the king of `player`
+// on the absolute board
+pub fn king_safety(board: &Board, player: Player, params: &EvalParams) -> (i32, i32) {
+  let king = board.get_pieceboard(player, Pieces::King).bitboard;
+  if king == 0 {
+    return (0, 0);
+  }
+
+  let ksq = king.trailing_zeros();
+  let own_pawns = board.get_pieceboard(player, Pieces::Pawn).bitboard;
+  let opp_pawns = board.get_pieceboard(player.other(), Pieces::Pawn).bitboard;
+  let mut mg = 0;
+  let mut eg = 0;
+
+  let mut files = file_mask(ksq) | adjacent_files(ksq);
+  while files != 0 {
+    // the square of the king's rank on this file, so the distances can be taken from there
+    let sq = (ksq & !7) | (files.trailing_zeros() & 7);
+    let file = file_mask(sq);
+    files &= !file;
+
+    if let Some(d @ 1..=2) = pawn_distance(player, sq, own_pawns) {
+      mg += params.shield[d as usize - 1].0;
+      eg += params.shield[d as usize - 1].1;
+    }
+    if let Some(d @ 1..=3) = pawn_distance(player, sq, opp_pawns) {
+      mg += params.storm[d as usize - 1].0;
+      eg += params.storm[d as usize - 1].1;
+    }
+
+    let open = match (own_pawns & file != 0, opp_pawns & file != 0) {
+      (false, false) => params.open_file,
+      (false, true) => params.semi_open_file,
+      _ => (0, 0),
+    };
+    mg += open.0;
+    eg += open.1;
+  }
+
+  // each piece that hits the king zone adds the weight of every square it attacks there
+  let zone = MoveGen::get_king_attacks(king) | king;
+  let free_mask = board.get_freesq_mask();
+  let mut attackers = 0;
+  let mut weight = (0, 0);
+
+  for piece in [Pieces::Rook, Pieces::Knight, Pieces::Bishop, Pieces::Queen] {
+    let mut pieces = board.get_pieceboard(player.other(), piece).bitboard;
+
+    while pieces != 0 {
+      let bit = 1u64 << pieces.trailing_zeros();
+      pieces ^= bit;
+
+      let attacks = piece_attacks(piece, bit, free_mask) & zone;
+
+      if attacks != 0 {
+        attackers += 1;
+        let n = attacks.count_ones() as i32;
+        weight.0 += params.king_attack[piece as usize].0 * n;
+        weight.1 += params.king_attack[piece as usize].1 * n;
+      }
+    }
+  }
+
+  let scale = params.king_attackers_scale[attackers.min(7)];
+  mg -= weight.0 * scale / 100;
+  eg -= weight.1 * scale / 100;
+
+  (mg, eg)
+}
+
+// 24 in the opening down to 0 in a pawn ending, promotions can not push it beyond the start value
+pub fn game_phase(s: &GameState) -> i32 {
+  let phase: i32 = PIECES.iter()
+    .map(|&piece| PHASE_WEIGHT[piece as usize] * (s.count_pieces(Player::White, piece) + s.count_pieces(Player::Black, piece)) as i32)
+    .sum();
+
+  phase.min(MAX_PHASE)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Term {
+  Material,
+  PieceSquare,
+  Pawns,
+  KingSafety,
+  Mobility,
+  Activity,
+}
+
+pub const TERMS: [Term; 6] = [Term::Material, Term::PieceSquare, Term::Pawns, Term::KingSafety, Term::Mobility, Term::Activity];
+
+impl Term {
+  pub fn name(&self) -> &'static str {
+    match self {
+      Term::Material => "material",
+      Term::PieceSquare => "piece-square",
+      Term::Pawns => "pawns",
+      Term::KingSafety => "king safety",
+      Term::Mobility => "mobility",
+      Term::Activity => "activity",
+    }
+  }
+}
+
+// every term of the evaluation before it is tapered
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Breakdown {
+  // (middlegame, endgame) indexed by Term and player, each from the player's own point of view
+  pub terms: [[(i32, i32); 2]; 6],
+  pub phase: i32,
+}
+
+impl Breakdown {
+  pub fn get(&self, term: Term, player: Player) -> (i32, i32) {
+    self.terms[term as usize][player as usize]
+  }
+
+  // white's value minus black's value
+  pub fn diff(&self, term: Term) -> (i32, i32) {
+    let (white, black) = (self.get(term, Player::White), self.get(term, Player::Black));
+    (white.0 - black.0, white.1 - black.1)
+  }
+
+  pub fn taper(&self, (mg, eg): (i32, i32)) -> Score {
+    Score::cp((mg * self.phase + eg * (MAX_PHASE - self.phase)) / MAX_PHASE)
+  }
+
+  // white minus black summed over all terms
+  pub fn sum(&self) -> (i32, i32) {
+    TERMS.iter().map(|&t| self.diff(t)).fold((0, 0), |acc, d| (acc.0 + d.0, acc.1 + d.1))
+  }
+
+  pub fn total(&self) -> Score {
+    self.taper(self.sum())
+  }
+}
+
+// one row per term, the last columns are white minus black
+impl fmt::Display for Breakdown {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "{:>12} | {:<13} | {:<13} | white - black", "term", "white", "black")?;
+    writeln!(f, "{:>12} | {:>6} {:>6} | {:>6} {:>6} | {:>6} {:>6} {:>6}", "", "mg", "eg", "mg", "eg", "mg", "eg", "cp")?;
+
+    for term in TERMS {
+      let (white, black, diff) = (self.get(term, Player::White), self.get(term, Player::Black), self.diff(term));
+      writeln!(f, "{:>12} | {:>6} {:>6} | {:>6} {:>6} | {:>6} {:>6} {:>6}",
+               term.name(), white.0, white.1, black.0, black.1, diff.0, diff.1, self.taper(diff).0)?;
+    }
+
+    let sum = self.sum();
+    writeln!(f, "{:>12} | {:13} | {:13} | {:>6} {:>6} {:>6}", "total", "", "", sum.0, sum.1, self.total().0)?;
+    write!(f, "phase {} of {}, total from white's point of view", self.phase, MAX_PHASE)
+  }
+}
+
+fn material(board: &Board, player: Player, params: &EvalParams) -> (i32, i32) {
+  PIECES.iter().fold((0, 0), |acc, &piece| {
+    let n = board.count_pieces(player, piece) as i32;
+    (acc.0 + n * params.material[piece as usize].0, acc.1 + n * params.material[piece as usize].1)
+  })
+}
+
+fn piece_square(board: &Board, player: Player, params: &EvalParams) -> (i32, i32) {
+  let mut mg = 0;
+  let mut eg = 0;
+
+  for piece in PIECES {
+    let mut pieces = board.get_pieceboard(player, piece).bitboard;
+
+    while pieces != 0 {
+      let idx = pst_index(player, pieces.trailing_zeros());
+      mg += params.pst_mg[piece as usize][idx];
+      eg += params.pst_eg[piece as usize][idx];
+      pieces &= pieces - 1;
+    }
+  }
+
+  (mg, eg)
+}
+
+// material, piece-square values, pawn structure, king safety, mobility and piece activity from white's
+// point of view, blended between the middlegame and the endgame values by the game phase
+pub fn eval(s: &GameState) -> Score {
+  eval_with(s, &DEFAULT_PARAMS)
+}
+
+pub fn eval_with(s: &GameState, params: &EvalParams) -> Score {
+  breakdown(s, params).total()
+}
+
+// the same as eval_with, the pawn structure is looked up in the table first. the table has to be
+// cleared when the parameters change
+pub fn eval_cached(s: &GameState, params: &EvalParams, pawn_table: &mut PawnTable) -> Score {
+  let board = s.get_absolute_board();
+  let entry = pawn_table.get(s.get_pawn_hash(), &board, params);
+  evaluate(s, &board, &entry, params).total()
+}
+
+pub fn breakdown(s: &GameState, params: &EvalParams) -> Breakdown {
+  let board = s.get_absolute_board();
+  evaluate(s, &board, &pawns::evaluate(&board, params), params)
+}
+
+fn evaluate(s: &GameState, board: &Board, pawn_entry: &PawnEntry, params: &EvalParams) -> Breakdown {
+  let mut ret = Breakdown {phase: game_phase(s), ..Breakdown::default()};
+
+  for player in [Player::White, Player::Black] {
+    let (pawns_mg, pawns_eg) = pawn_entry.score[player as usize];
+    let (free_mg, free_eg) = pawns::free_passers(pawn_entry, board, player, params);
+
+    let idx = player as usize;
+    ret.terms[Term::Material as usize][idx] = material(board, player, params);
+    ret.terms[Term::PieceSquare as usize][idx] = piece_square(board, player, params);
+    ret.terms[Term::Pawns as usize][idx] = (pawns_mg + free_mg, pawns_eg + free_eg);
+    ret.terms[Term::KingSafety as usize][idx] = king_safety(board, player, params);
+    ret.terms[Term::Mobility as usize][idx] = mobility(board, player, params);
+    ret.terms[Term::Activity as usize][idx] = piece_activity(board, player, params);
+  }
+
+  ret
+}
@@ -1,19 +1,37 @@
 use crate::board::{Board, Player, Pieces};
 use crate::movegen::{Move, MoveGen, CastleType};
+use crate::zobrist;
 
 pub const CASTLE_WHITE_KINGSIDE: u8 = 0b1 << 3;
 pub const CASTLE_WHITE_QUEENSIDE: u8 = 0b1 << 2;
 pub const CASTLE_BLACK_KINGSIDE: u8 = 0b1 << 1;
 pub const CASTLE_BLACK_QUEENSIDE: u8 = 0b1 << 0;
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WinReason {
+  Checkmate,
+  Resignation,
+  Timeout,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RemisReason {
+  Stalemate,
+  FiftyMoveRule,
+  ThreefoldRepetition,
+  InsufficientMaterial,
+  Agreement,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum GameResult {
-  Win(Player),
-  Remis,
+  Win(Player, WinReason),
+  Remis(RemisReason),
   NotDone,
 }
 
 pub fn algebraic_to_shift(pos: &str) -> Option<u32> {
-  if pos.len() != 0 {return None; }
+  if pos.len() != 2 {return None; }
 
   let mut chars = pos.chars();
 
@@ -24,10 +42,10 @@ pub fn algebraic_to_shift(pos: &str) -> Option<u32> {
   
   if !('1'..='8').contains(&rank) { return None;}
 
-  let file_idx = ((file as u8) - b'a') as u32;
-  let rank_idx = 7 - ((rank as u8) - b'1') as u32;
+  let file_idx = 7 - ((file as u8) - b'a') as u32;
+  let rank_idx = ((rank as u8) - b'1') as u32;
 
-  Some(file_idx * 8 + rank_idx)
+  Some(file_idx + rank_idx * 8)
 }
 
 #[derive(Copy, Clone)]
@@ -38,16 +56,24 @@ pub struct GameState {
   ep_square: Option<u32>,
   halfmove_clock: u32,
   fullmove_clock: u32,
+  hash: u64,
+  // only the pawns, the pawn structure evaluation is cached under this key
+  pawn_hash: u64,
 }
 
+#[derive(Clone)]
 pub struct History {
   history: Vec<GameState>,
   idx: i32,
 }
 
+#[derive(Clone)]
 pub struct Game {
   history: History,
   pub state: GameState,
+  // results that cannot be read from the board (resignation, timeout, agreed draw), with the length
+  // of the history when they were recorded. undoing a move played before takes them back
+  adjudication: Option<(usize, GameResult)>,
 }
 
 impl Game {
@@ -56,16 +82,20 @@ impl Game {
     match state {
       Ok(state) => {
         let history = History::new(state);
-        Ok(Game { state:state, history: history})
+        Ok(Game { state, history, adjudication: None})
       },
       Err(x) => Err(x),
     }
   }
 
   pub fn load_fen(&mut self, fen: &str) -> Result<(), &'static str> {
+    // a bad FEN leaves the game as it was
+    let state = GameState::from_fen(fen)?;
+
     self.history.clear(); 
+    self.adjudication = None;
 
-    self.state = GameState::from_fen(fen)?;
+    self.state = state;
     self.history.push(self.state);
 
     Ok(())
@@ -76,57 +106,8 @@ impl Game {
     MoveGen:: pseudo_legal(&self.state)
   }
 
-  fn is_legal_move(&mut self, m: &Move) -> bool {
-    // check not in check if castling
-    if let Some(x) = m.castling(){
-      if self.state.is_check(self.state.player) {
-        return false;
-      }
-
-      let attacks = self.state.get_attacks();
-
-      let path_free: bool = match x {
-        CastleType::Kingside => {
-          let ray = 1u64 << 2 | 1u64 << 1;
-          (attacks & ray) == 0
-        },
-        CastleType::Queenside => {
-          let ray = 1u64 << 4 | 1u64 << 5;
-          (attacks & ray) == 0
-        }
-      };
-
-      if !path_free {
-        return false
-      }
-    }
-
-    let player = self.state.player;
-
-    self.do_move(m);
-    if self.state.is_check(player) {
-      self.undo_move();
-      return false;
-    }
-    self.undo_move();
-
-    true
-
-  }
-
   fn exists_legal_move(&mut self) -> bool {
-    let player = self.state.get_player();
-
-    for m in self.moves() {
-      self.do_move(&m);
-      if !self.state.is_check(player) {
-        self.undo_move(); 
-        return true;
-      }
-      self.undo_move();
-    }
-    
-    false
+    !MoveGen::legal(&self.state).is_empty()
   }
 
 
@@ -138,6 +119,11 @@ impl Game {
   pub fn undo_move(&mut self) {
     self.history.pop();
 
+    let len = self.history.get_states().len();
+    if self.adjudication.is_some_and(|(at, _)| at > len) {
+      self.adjudication = None;
+    }
+
     self.state = match self.history.peek() {
       Some(x) => x,
       None => return,
@@ -145,15 +131,34 @@ impl Game {
   }
 
   pub fn get_result(&mut self) -> GameResult {
-    if self.is_checkmate(Player::White) {
-      return GameResult::Win(self.state.player.other());
+    if let Some((_, result)) = self.adjudication {
+      return result;
     }
 
-    if self.is_remis() {
-      return GameResult::Remis;
+    if self.is_checkmate(self.state.player) {
+      return GameResult::Win(self.state.player.other(), WinReason::Checkmate);
+    }
+
+    match self.remis_reason() {
+      Some(reason) => GameResult::Remis(reason),
+      None => GameResult::NotDone,
     }
+  }
+
+  pub fn resign(&mut self, player: Player) {
+    self.adjudicate(GameResult::Win(player.other(), WinReason::Resignation));
+  }
+
+  pub fn lose_on_time(&mut self, player: Player) {
+    self.adjudicate(GameResult::Win(player.other(), WinReason::Timeout));
+  }
 
-    GameResult::NotDone
+  pub fn agree_remis(&mut self) {
+    self.adjudicate(GameResult::Remis(RemisReason::Agreement));
+  }
+
+  fn adjudicate(&mut self, result: GameResult) {
+    self.adjudication = Some((self.history.get_states().len(), result));
   }
 
   pub fn makemove(&mut self, m: &Move) -> Result<(), &'static str> {
@@ -184,52 +189,95 @@ impl Game {
 
 
   pub fn legal_moves(&mut self) -> Vec<Move> {
-    let moves = self.moves();
-
-    let r = moves.into_iter().filter(|m| self.is_legal_move(m)).collect();
-    r
+    MoveGen::legal(&self.state)
   }
 
   pub fn is_remis(&mut self) -> bool {
-    if self.state.halfmove_clock >= 50 {
-      return true;
+    self.remis_reason().is_some()
+  }
+
+  // checkmate is not detected here, a mated position counts as stalemate
+  pub fn remis_reason(&mut self) -> Option<RemisReason> {
+    if self.is_insufficient_material() {
+      return Some(RemisReason::InsufficientMaterial);
+    }
+
+    if self.is_threefold_repetition() {
+      return Some(RemisReason::ThreefoldRepetition);
     }
 
     if !self.exists_legal_move() {
-      return true;
+      return Some(RemisReason::Stalemate);
+    }
+
+    if self.is_fifty_moves() {
+      return Some(RemisReason::FiftyMoveRule);
     }
 
-    false
+    None
   }
 
-  pub fn is_checkmate(&mut self, player: Player) -> bool {
-    if !self.state.is_check(player) {
-      return false; 
-    }
+  pub fn is_fifty_moves(&self) -> bool {
+    self.state.halfmove_clock >= 100
+  }
 
-    for m in self.moves() {
-      self.do_move(&m);
-      if !self.state.is_check(player) {
-        self.undo_move(); 
-        return false;
+  pub fn is_threefold_repetition(&self) -> bool {
+    self.history.count_repetitions(&self.state) >= 3
+  }
+
+  // KvK, KNvK, KBvK and KB vs KB with both bishops on the same colour
+  pub fn is_insufficient_material(&self) -> bool {
+    let board = self.state.get_relative_board();
+
+    for player in [Player::White, Player::Black] {
+      for piece in [Pieces::Pawn, Pieces::Rook, Pieces::Queen] {
+        if board.count_pieces(player, piece) != 0 {
+          return false;
+        }
       }
+    }
 
-      self.undo_move();
+    let minors = |player: Player| board.count_pieces(player, Pieces::Knight) + board.count_pieces(player, Pieces::Bishop);
+
+    match (minors(Player::White), minors(Player::Black)) {
+      (0, 0) | (1, 0) | (0, 1) => true,
+      (1, 1) => {
+        let light_squares = 0x55aa55aa55aa55aau64;
+        let white_bishop = board.get_pieceboard(Player::White, Pieces::Bishop).bitboard;
+        let black_bishop = board.get_pieceboard(Player::Black, Pieces::Bishop).bitboard;
+
+        if white_bishop == 0 || black_bishop == 0 {
+          return false;
+        }
+
+        (white_bishop & light_squares == 0) == (black_bishop & light_squares == 0)
+      },
+      _ => false,
     }
+  }
 
-    true
+  pub fn is_checkmate(&mut self, player: Player) -> bool {
+    if !self.state.is_check(player) {
+      return false; 
+    }
+
+    !self.exists_legal_move()
   }
 
   pub fn get_player(&self) -> Player {
     self.state.get_player()
   }
 
+  pub fn get_history(&self) -> &History {
+    &self.history
+  }
+
 }
 
 impl History {
   pub fn new(game: GameState) -> Self {
     let history = vec![game];
-    History {history: history, idx: 0}
+    History {history, idx: 0}
   }
 
   pub fn clear(&mut self) {
@@ -255,13 +303,29 @@ impl History {
     ret
   }
 
+  // counts how often the position of state occurred since the last irreversible move
+  pub fn count_repetitions(&self, state: &GameState) -> u32 {
+    let hash = state.get_hash();
+    let window = state.get_halfmove_clock() as usize;
+
+    self.history.iter()
+      .rev()
+      .take(window + 1)
+      .filter(|s| s.get_hash() == hash)
+      .count() as u32
+  }
+
+  // all positions of the game, starting with the initial one
+  pub fn get_states(&self) -> &[GameState] {
+    &self.history
+  }
+
   pub fn peek(&self) -> Option<GameState> {
     if self.idx < 0 {
       return None;
     }
-    
-    let ret = Some(self.history[self.idx as usize]);
-    ret
+
+    Some(self.history[self.idx as usize])
   }
 }
 
@@ -301,8 +365,8 @@ impl GameState {
       rank = 7 - rank;
     }
 
-    let file_c = ('a'..='h').into_iter().nth(file).unwrap();
-    let rank_c = ('1'..='8').into_iter().nth(rank).unwrap();
+    let file_c = ('a'..='h').nth(file).unwrap();
+    let rank_c = ('1'..='8').nth(rank).unwrap();
       
     let mut ret_str = String::with_capacity(2);
     ret_str.push(file_c);
@@ -350,34 +414,181 @@ impl GameState {
       None => return Err("Invalid FEN String. Castling rights not specified")
     };
 
+    // the ep square is stored as the square of the pawn that can be captured,
+    // relative to the player to move
     let ep_target =  match fields.next() {
-      Some(x) => algebraic_to_shift(x),
+      Some("-") => None,
+      Some(x) => match algebraic_to_shift(x) {
+        Some(target) => {
+          let target = if active == Player::Black { target ^ 56 } else { target };
+
+          if !(40..48).contains(&target) {
+            return Err("Invalid FEN String. En-passant target is on the wrong rank.");
+          }
+          Some(target - 8)
+        },
+        None => return Err("Invalid FEN String. En-passant target is not a square."),
+      },
       None => return Err("Invalid FEN String. No en-passant targets provided")
     };
 
     let half_moves: u32 = match fields.next() {
-      Some(x) => x.parse().expect("Invalid FEN String. halfmove clock is not a nubmer"),
+      Some(x) => x.parse().map_err(|_| "Invalid FEN String. Halfmove clock is not a number.")?,
       None => return Err("Invalid FEN String. Halfmove Clokc not specified."),
-    }
-    ;
+    };
     let full_moves: u32 = match fields.next() {
-      Some(x) => x.parse().expect("Invalid FEN String. Move clock is not a nubmer"),
+      Some(x) => x.parse().map_err(|_| "Invalid FEN String. Move clock is not a number.")?,
       None => return Err("Invalid FEN String. Move Clokc not specified."),
     };
+
+    // move generation and the search expect exactly one king per side
+    if board.count_pieces(Player::White, Pieces::King) != 1 || board.count_pieces(Player::Black, Pieces::King) != 1 {
+      return Err("Invalid FEN String. Each side must have exactly one king.");
+    }
  
  
-    let mut rel_board = board.clone();
+    let mut rel_board = board;
     if active == Player::Black {
       rel_board.flip();
     }
   
-    Ok(GameState {relative_board: rel_board,
+    let mut state = GameState {relative_board: rel_board,
                  player: active,
-                 castling: castling,
+                 castling,
                  ep_square: ep_target,
                   halfmove_clock: half_moves,
                   fullmove_clock: full_moves,
-              })
+                  hash: 0,
+                  pawn_hash: 0,
+              };
+    state.hash = state.compute_hash();
+    state.pawn_hash = state.compute_pawn_hash();
+
+    Ok(state)
+  }
+
+  // the ep square only goes into the hash if a pawn can take en passant, otherwise the same position
+  // would get a different hash depending on how it was reached. the fen keeps the square
+  fn hash_ep(&self) -> Option<u32> {
+    self.ep_square.filter(|_| MoveGen::can_take_en_passant(self))
+  }
+
+  pub fn compute_hash(&self) -> u64 {
+    let mut hash = 0u64;
+
+    for player in [Player::White, Player::Black] {
+      for piece in [Pieces::Pawn, Pieces::Rook, Pieces::Knight, Pieces::Bishop, Pieces::Queen, Pieces::King] {
+        let mut pieces = self.relative_board.get_pieceboard(player, piece).bitboard;
+
+        while pieces != 0 {
+          let sq = pieces.trailing_zeros();
+          hash ^= zobrist::piece_key(player, piece, self.absolute_shift(sq));
+          pieces ^= 1u64 << sq;
+        }
+      }
+    }
+
+    if self.player == Player::Black {
+      hash ^= zobrist::side_key();
+    }
+
+    hash ^ zobrist::castling_key(self.castling) ^ zobrist::ep_key(self.hash_ep())
+  }
+
+  pub fn compute_pawn_hash(&self) -> u64 {
+    let mut hash = 0u64;
+
+    for player in [Player::White, Player::Black] {
+      let mut pawns = self.relative_board.get_pieceboard(player, Pieces::Pawn).bitboard;
+
+      while pawns != 0 {
+        let sq = pawns.trailing_zeros();
+        hash ^= zobrist::piece_key(player, Pieces::Pawn, self.absolute_shift(sq));
+        pawns ^= 1u64 << sq;
+      }
+    }
+
+    hash
+  }
+
+  // converts a square of the relative board to white's point of view
+  #[inline]
+  fn absolute_shift(&self, shift: u32) -> u32 {
+    match self.player {
+      Player::White => shift,
+      Player::Black => shift ^ 56,
+    }
+  }
+
+  // flips a piece on the relative board and keeps the hash in sync
+  fn toggle_piece(&mut self, player: Player, piece: Pieces, shift: u32) {
+    self.relative_board.flip_piece(player, piece, shift as i32).unwrap();
+    self.hash ^= zobrist::piece_key(player, piece, self.absolute_shift(shift));
+    if piece == Pieces::Pawn {
+      self.pawn_hash ^= zobrist::piece_key(player, piece, self.absolute_shift(shift));
+    }
+  }
+
+  pub fn to_fen(self) -> String {
+    let board = self.get_absolute_board();
+
+    let active = match self.player {
+      Player::White => "w",
+      Player::Black => "b",
+    };
+
+    let mut castling = String::new();
+    for (mask, sym) in [(CASTLE_WHITE_KINGSIDE, 'K'), (CASTLE_WHITE_QUEENSIDE, 'Q'),
+                        (CASTLE_BLACK_KINGSIDE, 'k'), (CASTLE_BLACK_QUEENSIDE, 'q')] {
+      if self.castling & mask != 0 {
+        castling.push(sym);
+      }
+    }
+    if castling.is_empty() {
+      castling.push('-');
+    }
+
+    // target square lies one rank behind the pawn that can be captured
+    let ep_target = self.ep_square
+      .and_then(|sq| self.shift_to_algebraic(sq + 8))
+      .unwrap_or_else(|| String::from("-"));
+
+    format!("{} {} {} {} {} {}", board.to_fen(), active, castling, ep_target, self.halfmove_clock, self.fullmove_clock)
+  }
+
+  pub fn is_legal_move(&self, m: &Move) -> bool {
+    let mut state = *self;
+
+    // check not in check if castling
+    if let Some(x) = m.castling(){
+      if state.is_check(state.player) {
+        return false;
+      }
+
+      let attacks = state.get_attacks();
+
+      let path_free: bool = match x {
+        CastleType::Kingside => {
+          let ray = 1u64 << 2 | 1u64 << 1;
+          (attacks & ray) == 0
+        },
+        CastleType::Queenside => {
+          let ray = 1u64 << 4 | 1u64 << 5;
+          (attacks & ray) == 0
+        }
+      };
+
+      if !path_free {
+        return false
+      }
+    }
+
+    state.make_move(m);
+    !state.is_check(self.player)
+  }
+
+  pub fn legal_moves(&self) -> Vec<Move> {
+    MoveGen::legal(self)
   }
 
   pub fn get_attacks(&mut self) -> u64 {
@@ -418,6 +629,8 @@ impl GameState {
   }
 pub fn make_move(&mut self, m: &Move) {
     let piece = m.piece;
+    let old_castling = self.castling;
+    let old_ep = self.hash_ep();
     let next_player = match self.player {
       Player::White => {
         self.halfmove_clock += 1;
@@ -446,42 +659,41 @@ pub fn make_move(&mut self, m: &Move) {
       }
 
       // capture piece
-      self.relative_board.flip_piece(x, y, m.to as i32).unwrap();
-      self.halfmove_clock = 1; 
-      self.fullmove_clock = 0;
+      self.toggle_piece(x, y, m.to);
+      self.halfmove_clock = 0;
     }
 
     // move piece
-    self.relative_board.flip_piece(self.player, piece, m.from as i32).unwrap();
-    self.relative_board.flip_piece(self.player, piece, m.to as i32).unwrap();
+    self.toggle_piece(self.player, piece, m.from);
+    self.toggle_piece(self.player, piece, m.to);
 
     self.ep_square = None;
 
     // extra handling
     match piece {
       Pieces::Pawn => {
+        self.halfmove_clock = 0;
+
         if let Some(p) = m.promotion {
-          self.relative_board.flip_piece(self.player, p, m.to as i32).unwrap();
+          self.toggle_piece(self.player, p, m.to);
           // unset pawn
-          self.relative_board.flip_piece(self.player, Pieces::Pawn, m.to as i32).unwrap();
-        } else if m.ep == true {
-          self.relative_board.flip_piece(next_player, piece, (m.to - 8) as i32).unwrap();
+          self.toggle_piece(self.player, Pieces::Pawn, m.to);
+        } else if m.ep {
+          self.toggle_piece(next_player, piece, m.to - 8);
         } else if m.to - m.from == 16 {
           let row = m.to / 8;
           let col = m.to % 8;
 
           self.ep_square = Some(((7 - row) * 8) + col);
-          self.halfmove_clock = 1;
-          self.fullmove_clock = 0;
         }
       },
       Pieces::King => {
         if m.from == 3 && m.to == 1 {
-          self.relative_board.flip_piece(self.player, Pieces::Rook, 0).unwrap();
-          self.relative_board.flip_piece(self.player, Pieces::Rook, 2).unwrap();
+          self.toggle_piece(self.player, Pieces::Rook, 0);
+          self.toggle_piece(self.player, Pieces::Rook, 2);
         } else if m.from == 3 && m.to == 5 {
-          self.relative_board.flip_piece(self.player, Pieces::Rook, 7).unwrap();
-          self.relative_board.flip_piece(self.player, Pieces::Rook, 4).unwrap();
+          self.toggle_piece(self.player, Pieces::Rook, 7);
+          self.toggle_piece(self.player, Pieces::Rook, 4);
         }
 
         // remove castling rights
@@ -509,6 +721,13 @@ pub fn make_move(&mut self, m: &Move) {
 
     self.relative_board.flip();
     self.player = next_player;
+
+    self.hash ^= zobrist::side_key() ^
+                 zobrist::castling_key(old_castling) ^ zobrist::castling_key(self.castling) ^
+                 zobrist::ep_key(old_ep) ^ zobrist::ep_key(self.hash_ep());
+
+    debug_assert_eq!(self.hash, self.compute_hash(), "Incremental hash diverged from the position");
+    debug_assert_eq!(self.pawn_hash, self.compute_pawn_hash(), "Incremental pawn hash diverged from the position");
   }
 
   pub fn count_pieces(&self, player: Player, piece: Pieces) -> u32 {
@@ -519,6 +738,15 @@ pub fn make_move(&mut self, m: &Move) {
     self.relative_board
   }
 
+  // the board from white's point of view, no matter who is to move
+  pub fn get_absolute_board(&self) -> Board {
+    let mut board = self.relative_board;
+    if self.player == Player::Black {
+      board.flip();
+    }
+    board
+  }
+
   pub fn get_player(&self) -> Player {
     self.player
   }
@@ -531,6 +759,22 @@ pub fn make_move(&mut self, m: &Move) {
     self.castling
   }
 
+  pub fn get_hash(&self) -> u64 {
+    self.hash
+  }
+
+  pub fn get_pawn_hash(&self) -> u64 {
+    self.pawn_hash
+  }
+
+  pub fn get_halfmove_clock(&self) -> u32 {
+    self.halfmove_clock
+  }
+
+  pub fn get_fullmove_clock(&self) -> u32 {
+    self.fullmove_clock
+  }
+
   pub fn print_state(&mut self) {
     match self.player {
       Player::White => println!("Player is white"),
@@ -1,8 +1,18 @@
 pub mod board;
+pub mod eval;
 pub mod movegen;
 pub mod game;
+pub mod magic;
+pub mod pawns;
+pub mod params;
 pub mod perft;
+pub mod pgn;
+pub mod score;
 pub mod search;
+pub mod tt;
+pub mod tune;
+pub mod uci;
+pub mod zobrist;
 
 pub use board::Player;
 pub use board::Pieces;
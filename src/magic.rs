@@ -0,0 +1,192 @@
+// sliding attacks looked up with magic bitboards. the magics were found once by trying sparse random
+// numbers, the attack tables are built by the compiler so a lookup never has to check whether they
+// are ready. a magic that maps two subsets with different attacks to one entry fails the build
+
+pub static ROOK_MAGICS: [u64; 64] = [
+0x2080002080400010, 0x00c0002001401000, 0x2100110008402002, 0x0880080081041000, 0x0200020020041008, 0x2300040008010012, 0x0c00283004008201, 0x0180010000407a80, 0x0168800080400020, 0x0010400040201000, 0x1001002001001048, 0x1001002408100100, 0x0801000408010012, 0x4001000209000400, 0x08a20004c8020001, 0x2002801145002280, 0x0080860021004200, 0x001000c009402002, 0x00b0002004002800, 0x100a808010020800, 0x8101010008000410, 0x0244008002000480, 0x0000040010810208, 0x2000020000448534, 0x4104400480008033, 0x0000810100204000, 0x0440430900200010, 0x4600240900100100, 0x0060080080040080, 0x0001000300080400, 0x0004084400011002, 0x0023040200008041, 0x0580050043002080, 0x0400804002802008, 0x0001002001004010, 0x1000200901001000, 0x4410800801800c00, 0xa012003806001004, 0x0020100104008802, 0x0004808402000041, 0x0010400170898000, 0x0080500020004004, 0x1040408012020020, 0x8010040008004040, 0x2001080100110004, 0x0000020004008080, 0x0021010810040002, 0x0800008c43020024, 0x0000800021005100, 0x0070201040008080, 0x0000d04282006a00, 0x0010014400080240, 0x0001080110050100, 0x0012000810240600, 0x0402000801040200, 0x028100108a004100, 0x0050800300102045, 0x8208210040120882, 0x8010600101183441, 0x020b000910006045, 0x0241001002480005, 0x0081000400880241, 0x0000009008024124, 0x0048122980410402
+];
+
+pub static BISHOP_MAGICS: [u64; 64] = [
+0x0848020822040013, 0x8010a40085821200, 0x0008008430840822, 0x0808048108040000, 0x1304042100008104, 0x5001012010204023, 0x81048801b8200420, 0x200a008084012000, 0x0040102001042084, 0x840a505042428020, 0x0000700102202920, 0x44101c0c10800002, 0x0040040422000000, 0x0180020802090202, 0x4020020811041202, 0x000104308c042000, 0x4140661002424400, 0x0028012008010460, 0x0188062102002a00, 0x0014004840102008, 0x0105000290400002, 0x8001022200410400, 0x104a041918013446, 0x008a000082008238, 0x04a0060008100430, 0x0008220008820801, 0x2508041208005010, 0x4008080200202020, 0x2441001013004000, 0x0030008060407000, 0x4008108000420800, 0x0012021050290100, 0x0210080482200500, 0xcc01112048100480, 0x0020402806500440, 0x00048e0080580080, 0x0040102020020080, 0x0028010440080807, 0x4601041108008800, 0x8040810e04104200, 0x901210110400088a, 0xa003080212081050, 0x00c1004048401004, 0x900000a014400800, 0x0008021040405401, 0x4020008206002090, 0x0004190424030100, 0x0424008a02026250, 0x8004088250900040, 0x1c00430088a04200, 0x0001020094040001, 0x8040210020880061, 0x2010040450442032, 0x0800840850044001, 0x0004040802140004, 0x0004080a04222020, 0x8088802110022000, 0x1081a10416114400, 0x0205010a24060820, 0x0000000720411080, 0x1008000208430400, 0x580c026028810840, 0x802020441020a110, 0x12c0022401020018
+];
+
+// file and rank steps, a ray ends at the edge of the board and never wraps to the next rank
+const ROOK_DIRS: [(i32, i32); 4] = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+const BISHOP_DIRS: [(i32, i32); 4] = [(1, 1), (-1, 1), (1, -1), (-1, -1)];
+
+// walks the ray one square at a time, the first blocker is included
+const fn ray_mask(from: u32, blocker_mask: u64, (file_step, rank_step): (i32, i32)) -> u64 {
+  let mut ret = 0;
+  let mut file = (from % 8) as i32 + file_step;
+  let mut rank = (from / 8) as i32 + rank_step;
+
+  while file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+    let to_mask = 1u64 << (rank * 8 + file);
+
+    ret |= to_mask;
+    file += file_step;
+    rank += rank_step;
+
+    if to_mask & blocker_mask != 0 {
+      break;
+    }
+  }
+
+  ret
+}
+
+// the last square of a ray on an empty board, the rays with a positive step end on the highest bit
+const fn without_edge(ray: u64, (file_step, rank_step): (i32, i32)) -> u64 {
+  if ray == 0 {
+    0
+  } else if rank_step * 8 + file_step > 0 {
+    ray & !(1u64 << (63 - ray.leading_zeros()))
+  } else {
+    ray & (ray - 1)
+  }
+}
+
+// the squares whose occupancy changes the attacks, the edges never block anything behind them
+const fn mask(from: u32, dirs: &[(i32, i32); 4]) -> u64 {
+  let mut ret = 0;
+  let mut i = 0;
+  while i < 4 {
+    ret |= without_edge(ray_mask(from, 0, dirs[i]), dirs[i]);
+    i += 1;
+  }
+
+  ret
+}
+
+const fn empty_rays(dirs: &[(i32, i32); 4]) -> [[u64; 64]; 4] {
+  let mut ret = [[0; 64]; 4];
+  let mut i = 0;
+  while i < 4 {
+    let mut sq = 0;
+    while sq < 64 {
+      ret[i][sq] = ray_mask(sq as u32, 0, dirs[i]);
+      sq += 1;
+    }
+    i += 1;
+  }
+
+  ret
+}
+
+const ROOK_RAYS: [[u64; 64]; 4] = empty_rays(&ROOK_DIRS);
+const BISHOP_RAYS: [[u64; 64]; 4] = empty_rays(&BISHOP_DIRS);
+
+// every ray stops at the blocker closest to `from`, the same as walking it. walking the rays for
+// every subset takes the compiler too long
+const fn blocked_rays(from: u32, occupied: u64, dirs: &[(i32, i32); 4], empty: &[[u64; 64]; 4]) -> u64 {
+  let mut ret = 0;
+  let mut i = 0;
+  while i < 4 {
+    let ray = empty[i][from as usize];
+    let blockers = ray & occupied;
+    let (file_step, rank_step) = dirs[i];
+
+    ret |= if blockers == 0 {
+      ray
+    } else if rank_step * 8 + file_step > 0 {
+      ray & !empty[i][blockers.trailing_zeros() as usize]
+    } else {
+      ray & !empty[i][63 - blockers.leading_zeros() as usize]
+    };
+    i += 1;
+  }
+
+  ret
+}
+
+#[derive(Clone, Copy)]
+struct Magic {
+  mask: u64,
+  magic: u64,
+  shift: u32,
+  offset: usize,
+}
+
+impl Magic {
+  const EMPTY: Magic = Magic {mask: 0, magic: 0, shift: 0, offset: 0};
+
+  #[inline]
+  const fn index(&self, occupied: u64) -> usize {
+    self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+  }
+}
+
+// every square has one entry per subset of its mask
+const fn table_size() -> usize {
+  let mut size = 0;
+  let mut sq = 0;
+  while sq < 64 {
+    size += 1 << mask(sq, &ROOK_DIRS).count_ones();
+    size += 1 << mask(sq, &BISHOP_DIRS).count_ones();
+    sq += 1;
+  }
+
+  size
+}
+
+const TABLE_SIZE: usize = table_size();
+
+struct Tables {
+  rook: [Magic; 64],
+  bishop: [Magic; 64],
+  // the attacks of every square one after the other, `Magic::offset` points to the first one
+  attacks: [u64; TABLE_SIZE],
+}
+
+static TABLES: Tables = build_tables();
+
+// the attacks for every subset of the mask, the magic maps subsets with different attacks apart
+const fn fill(from: u32, magic: u64, dirs: &[(i32, i32); 4], empty: &[[u64; 64]; 4], offset: usize, attacks: &mut [u64; TABLE_SIZE]) -> Magic {
+  let mask = mask(from, dirs);
+  let entry = Magic {mask, magic, shift: 64 - mask.count_ones(), offset};
+
+  // carry-rippler, walks every subset of the mask starting with the empty one
+  let mut subset = 0u64;
+  loop {
+    let i = entry.index(subset);
+    let att = blocked_rays(from, subset, dirs, empty);
+    // a slider always attacks something, so an empty entry has not been filled yet
+    assert!(attacks[i] == 0 || attacks[i] == att, "magic maps subsets with different attacks to one entry");
+    attacks[i] = att;
+
+    subset = subset.wrapping_sub(mask) & mask;
+    if subset == 0 {
+      break;
+    }
+  }
+
+  entry
+}
+
+const fn build_tables() -> Tables {
+  let mut attacks = [0; TABLE_SIZE];
+  let mut rook = [Magic::EMPTY; 64];
+  let mut bishop = [Magic::EMPTY; 64];
+  let mut offset = 0;
+
+  let mut sq = 0;
+  while sq < 64 {
+    rook[sq] = fill(sq as u32, ROOK_MAGICS[sq], &ROOK_DIRS, &ROOK_RAYS, offset, &mut attacks);
+    offset += 1 << (64 - rook[sq].shift);
+    bishop[sq] = fill(sq as u32, BISHOP_MAGICS[sq], &BISHOP_DIRS, &BISHOP_RAYS, offset, &mut attacks);
+    offset += 1 << (64 - bishop[sq].shift);
+    sq += 1;
+  }
+
+  Tables {rook, bishop, attacks}
+}
+
+#[inline]
+pub fn rook_attacks(from: u32, occupied: u64) -> u64 {
+  TABLES.attacks[TABLES.rook[from as usize].index(occupied)]
+}
+
+#[inline]
+pub fn bishop_attacks(from: u32, occupied: u64) -> u64 {
+  TABLES.attacks[TABLES.bishop[from as usize].index(occupied)]
+}
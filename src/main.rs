@@ -1,139 +1,63 @@
+use std::io::{self, Stdout};
+
 use rustyline::{DefaultEditor};
-use rustyline;
 use rustyline::error::ReadlineError;
 use itertools::Itertools;
 
-use crate::game::{Game, GameResult};
-use crate::board::{Player, BitBoard};
-use crate::movegen::{Move, MoveGen};
-//use crate::perft;
-
-mod board;
-mod game;
-mod movegen;
-mod perft;
-mod search;
-
-pub enum UciMode {
-  Normal,
-  Debug,
-}
-
-
-fn parse_position<'a, I >(tokens: &mut I, game: &mut Game) -> Result<(), &'static str>
-where
-  I: Iterator<Item = &'a str>,
-{
-  // load position
-  match tokens.next() {
-    Some("startpos") => {
-      game.load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")?;
-    },
-    Some("fen") => {
-      let fen_str = tokens.take(6).join(" "); 
-      
-      game.load_fen(&fen_str)?;
+use rustchess::game::{GameResult, WinReason, RemisReason};
+use rustchess::board::{Player, BitBoard};
+use rustchess::movegen::{Move, MoveGen};
+use rustchess::uci::{Uci};
+use rustchess::params::{EvalParams};
+use rustchess::tune::{self, TuneOptions};
+
+fn report_result(result: &GameResult) {
+  match result {
+    GameResult::Win(player, reason) => {
+      let winner = match player {
+        Player::White => "White",
+        Player::Black => "Black",
+      };
+      let reason = match reason {
+        WinReason::Checkmate => "checkmate",
+        WinReason::Resignation => "resignation",
+        WinReason::Timeout => "timeout",
+      };
+      println!("info string {} has won by {}", winner, reason);
     },
-    _ => {
-      return Err("Position not recognized");
-    }
-  }
-
-  // make moves
-  match tokens.next() {
-    Some("move") => {
-      while let Some(m_fen) = tokens.next() {
-        let m: Move = Move::from_lan(m_fen, &game.state).unwrap();
-        
-        match game.makemove(&m) {
-          Err(_) => return Err("Could not make move"),
-          Ok(_) => (),
-        }
-      }
+    GameResult::Remis(reason) => {
+      let reason = match reason {
+        RemisReason::Stalemate => "stalemate",
+        RemisReason::FiftyMoveRule => "fifty-move rule",
+        RemisReason::ThreefoldRepetition => "threefold repetition",
+        RemisReason::InsufficientMaterial => "insufficient material",
+        RemisReason::Agreement => "agreement",
+      };
+      println!("info string Remis by {}", reason);
     },
-    None => return Ok(()),
-    _ => println!("Not passed correctly"),
+    GameResult::NotDone => (),
   }
-
-  Ok(())
 }
 
-fn uci(s: &str, game: &mut Game, mode: &mut UciMode) {
+// REPL only commands, everything else is passed on to the UCI engine
+fn repl(s: &str, engine: &mut Uci<Stdout>) -> io::Result<bool> {
+  let game = &mut engine.game;
   let mut tokens = s.split_whitespace();
 
   match tokens.next() {
-    Some("uci") => {
-      println!("id name rustchess");
-      println!("id author scriptus_longus");
-      println!("uciok");
-    },
-
-    Some("debug") => {
-      *mode = match mode {
-        UciMode::Debug => UciMode::Normal,
-        UciMode::Normal => UciMode::Debug,
-      }
-    },
-
-    Some("isready") => {
-      println!("readyok");
-    },
-    Some("ucinewgame") => {
-      println!("");
-    },
-    Some("position") => {
-      match parse_position(&mut tokens, game) {
-        Ok(_) => {
-          println!("info string success");
-          game.state.print_state();
-        },
-        Err(x) => println!("info string Error: {}", x),
-      }
-    },
-    Some("go") => {
-      match tokens.next() {
-        Some("perft") => {
-          let depth = match tokens.next() {
-            Some(x) => match x.chars().next().unwrap().to_digit(10) {
-              Some(d) => d as usize,
-              _ => 0,
-            },
-            _ => 0,
-          };
-          let n = match mode {
-            UciMode::Normal => perft::perft(game, depth),
-            UciMode::Debug => perft::debug_perft(game, depth, true),
-          };
-
-          println!();
-          println!("{}", n);
-        },
-        _ => {
-
-          let (m, _) = search::root_search(game, 4);
-          let lan = match m {
-            Some(x) => Move::to_lan(&x, &game.state).unwrap(),
-            None => String::from("0000"),
-          };
-
-          println!("bestmove {}", lan);
-        },
-      }
-
-    },
     Some("makemove") => {
       let m = match tokens.next() {
         Some(x) => {
 
           match Move::from_lan(x, &game.state) {
             Ok(x) => x,
-            Err(x) => {println!("info string Not a valid move {}", x); return},
+            Err(x) => {println!("info string Not a valid move {}", x); return Ok(true)},
           }
 
         }
         None => {
           println!("info string No move was given");
-          return;
+          return Ok(true);
         }
       };
 
@@ -141,12 +65,7 @@ fn uci(s: &str, game: &mut Game, mode: &mut UciMode) {
         match game.makemove(&m) {
           Ok(_) => {
             game.state.print_state();
-            match game.get_result() {
-              GameResult::Win(Player::White) => println!("info string White has won! CHECKMATE for black"),
-              GameResult::Win(Player::Black) => println!("info string Black has won! CHECKMATE for white"),
-              GameResult::Remis => println!("info string Remis"),
-              _ => (),
-            }
+            report_result(&game.get_result());
           }
           Err(_) => {
             println!("info string invalid move")
@@ -173,12 +92,18 @@ fn uci(s: &str, game: &mut Game, mode: &mut UciMode) {
     Some("print") => {
       game.state.print_state();
     },
-    _ => (),
+    Some("position") => {
+      let running = engine.handle(s)?;
+      engine.game.state.print_state();
+      return Ok(running);
+    },
+    _ => return engine.handle(s),
   }
+
+  Ok(true)
 }
 
-fn main() -> rustyline::Result<()> {  
-//fn main() {
+fn run_repl() -> rustyline::Result<()> {
   println!("   ___ _               _            ___ ");
   println!("  / __\\ |__   ___  ___| | __ /\\/\\  ( _ )");
   println!(" / /  | '_ \\ / _ \\/ __| |/ //    \\ / _ \\");
@@ -186,9 +111,8 @@ fn main() -> rustyline::Result<()> {
   println!("\\____/|_| |_|\\___|\\___|_|\\_\\/    \\/\\___/");
   println!();                                         
 
-  
-  let mut game = Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
-  let mut mode = UciMode::Normal;
+  let mut engine = Uci::new(io::stdout());
+  engine.game.load_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
 
   let mut rl = DefaultEditor::new()?;
   loop {
@@ -198,14 +122,12 @@ fn main() -> rustyline::Result<()> {
       Ok(l) => {
         rl.add_history_entry(l.as_str())?;
 
-        if l == "exit" {
+        if l == "exit" || !repl(&l, &mut engine)? {
           println!("Exiting...");
           break;
-        } else {
-          uci(&l, &mut game, &mut mode);
         }
       },
-      Err(ReadlineError::Interrupted) => {
+      Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => {
         println!("Exiting...");
         break;
       }
@@ -218,3 +140,61 @@ fn main() -> rustyline::Result<()> {
   Ok(())
 }
 
+// rustchess tune <positions> <output> [--threads n] [--iterations n] [--params file]
+fn run_tune(args: &[String]) -> Result<(), &'static str> {
+  let (positions, output) = match args {
+    [positions, output, ..] => (positions, output),
+    _ => return Err("Usage: tune <positions> <output> [--threads n] [--iterations n] [--params file]"),
+  };
+
+  let mut options = TuneOptions::default();
+  let mut params = EvalParams::default();
+  let mut flags = args[2..].iter();
+
+  while let Some(flag) = flags.next() {
+    let value = flags.next().ok_or("Missing value for a tune option")?;
+    match flag.as_str() {
+      "--threads" => options.threads = value.parse().map_err(|_| "threads must be a number")?,
+      "--iterations" => options.iterations = value.parse().map_err(|_| "iterations must be a number")?,
+      "--params" => params = EvalParams::load(value)?,
+      _ => return Err("Unknown tune option"),
+    }
+  }
+
+  let entries = tune::load_entries(positions)?;
+  println!("{} positions, {} threads", entries.len(), options.threads);
+
+  let k = tune::fit_k(&entries, &params, options.threads);
+  println!("k {:.3}, error {:.6}", k, tune::error(&entries, &params, k, options.threads));
+
+  // the file is written after every pass so a long run can be stopped at any time
+  tune::tune(&entries, &params, k, &options, |pass, error, params| {
+    println!("pass {}, error {:.6}", pass, error);
+    if params.save(output).is_err() {
+      println!("Could not write {}", output);
+    }
+  });
+
+  Ok(())
+}
+
+fn main() -> rustyline::Result<()> {
+  // plain UCI on stdin by default, the interactive REPL only on request
+  if std::env::args().any(|a| a == "--repl") {
+    return run_repl();
+  }
+
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  if args.first().map(String::as_str) == Some("tune") {
+    if let Err(x) = run_tune(&args[1..]) {
+      eprintln!("{}", x);
+      std::process::exit(1);
+    }
+    return Ok(());
+  }
+
+  let stdin = io::stdin();
+  Uci::new(io::stdout()).run(stdin.lock())?;
+
+  Ok(())
+}
@@ -1,5 +1,6 @@
 use crate::board::{Board, Player, Pieces};
-use crate::game::{GameState};
+use crate::game::{Game, GameState};
+use crate::magic;
 use crate::game::{CASTLE_WHITE_KINGSIDE, CASTLE_WHITE_QUEENSIDE, CASTLE_BLACK_KINGSIDE, CASTLE_BLACK_QUEENSIDE};
 
 //use crate::game;
@@ -31,8 +32,8 @@ pub enum CastleType {
 impl Move {
   pub fn from_lan(lan: &str, state: &GameState) -> Result<Self, &'static str> {
 
-    if lan.len() < 4 {
-      return Err("Not a valid move");
+    if !lan.is_ascii() || (lan.len() != 4 && lan.len() != 5) {
+      return Err("Move must have 4 or 5 characters");
     }
 
     let from = match state.algebraic_to_shift(&lan[0..2]) {
@@ -45,32 +46,43 @@ impl Move {
       None => return Err("Could not convert target to LAN"),
     };
 
-    let mut promotion = None;
-    if lan.len() == 5 {
-      promotion = match lan.chars().nth(4).unwrap() {
-        'q' => Some(Pieces::Queen),
-        'r' => Some(Pieces::Rook),
-        'n' => Some(Pieces::Knight),
-        'b' => Some(Pieces::Bishop),
-        _ => None
-      }
+    if from == to {
+      return Err("Start and target square are the same");
     }
 
+    let promotion = match lan.chars().nth(4) {
+      Some('q') => Some(Pieces::Queen),
+      Some('r') => Some(Pieces::Rook),
+      Some('n') => Some(Pieces::Knight),
+      Some('b') => Some(Pieces::Bishop),
+      Some(_) => return Err("Promotion piece must be one of q, r, b, n"),
+      None => None,
+    };
+
     // get piece
     let piece = match state.relative_board.get_piece(from as i32) {
-      Some((_, x)) => x,
+      Some((player, x)) if player == state.get_player() => x,
+      Some(_) => return Err("Square is occupied by the opponent"),
       None => return Err("Square is not occupied"),
     };
 
+    // promotion piece is required exactly for pawns reaching the last rank
+    let last_rank = to >= 56;
+    if piece == Pieces::Pawn && last_rank && promotion.is_none() {
+      return Err("Promotion piece is missing");
+    }
+    if promotion.is_some() && (piece != Pieces::Pawn || !last_rank) {
+      return Err("Only pawns reaching the last rank can promote");
+    }
+
     //check ep
     let mut ep = false;
-    if let Some(x) = state.get_ep() {
-      if piece == Pieces::Pawn && to - 8 == x {
-        ep = true;
-      }
+    if let Some(x) = state.get_ep()
+      && piece == Pieces::Pawn && x + 8 == to && from % 8 != to % 8 {
+      ep = true;
     }
 
-    Ok(Move {piece: piece, from: from, to: to, promotion: promotion, ep: ep})
+    Ok(Move {piece, from, to, promotion, ep})
   }
 
   pub fn to_lan(m: &Move, state: &GameState) -> Result<String, &'static str> {
@@ -84,12 +96,168 @@ impl Move {
       None => return Err("Not a valid move"),
     };
 
+    from.push_str(&to);
 
+    match m.promotion {
+      Some(Pieces::Queen) => from.push('q'),
+      Some(Pieces::Rook) => from.push('r'),
+      Some(Pieces::Knight) => from.push('n'),
+      Some(Pieces::Bishop) => from.push('b'),
+      Some(_) => return Err("Not a valid promotion piece"),
+      None => (),
+    }
 
-    from.push_str(&to);
     Ok(from)
   }
 
+  pub fn from_san(san: &str, state: &GameState) -> Result<Self, &'static str> {
+    let san = san.trim_end_matches(['+', '#']);
+    let legal = state.legal_moves();
+
+    let candidates: Vec<Move> = match san {
+      "O-O" | "0-0" => legal.into_iter().filter(|m| matches!(m.castling(), Some(CastleType::Kingside))).collect(),
+      "O-O-O" | "0-0-0" => legal.into_iter().filter(|m| matches!(m.castling(), Some(CastleType::Queenside))).collect(),
+      _ => {
+        let mut chars: Vec<char> = san.chars().collect();
+
+        // promotion suffix, either "=Q" or "Q"
+        let promotion = match chars.last().and_then(|c| piece_from_san(*c)) {
+          Some(p) => {
+            chars.pop();
+            if chars.last() == Some(&'=') {
+              chars.pop();
+            }
+            Some(p)
+          },
+          None => None,
+        };
+
+        if chars.len() < 2 {
+          return Err("Not a valid move");
+        }
+
+        let target: String = chars[chars.len() - 2..].iter().collect();
+        let to = match state.algebraic_to_shift(&target) {
+          Some(x) => x,
+          None => return Err("Could not convert target to SAN"),
+        };
+
+        let prefix = &chars[..chars.len() - 2];
+        let (piece, prefix) = match prefix.first().and_then(|c| piece_from_san(*c)) {
+          Some(p) => (p, &prefix[1..]),
+          None => (Pieces::Pawn, prefix),
+        };
+
+        if promotion.is_some() && piece != Pieces::Pawn {
+          return Err("Only pawns can promote");
+        }
+
+        let mut from_file = None;
+        let mut from_rank = None;
+        let mut capture = false;
+        for c in prefix.iter() {
+          match c {
+            'a'..='h' if from_file.is_none() => from_file = Some(*c),
+            '1'..='8' if from_rank.is_none() => from_rank = Some(*c),
+            'x' if !capture => capture = true,
+            _ => return Err("Could not parse disambiguation"),
+          }
+        }
+
+        if capture && state.relative_board.get_piece(to as i32).is_none() &&
+           !(piece == Pieces::Pawn && state.get_ep().is_some_and(|ep| ep + 8 == to)) {
+          return Err("Move is marked as capture but target square is empty");
+        }
+
+        legal.into_iter().filter(|m| {
+          let from = state.shift_to_algebraic(m.from).unwrap_or_default();
+
+          m.piece == piece && m.to == to && m.promotion == promotion && m.castling().is_none() &&
+            from_file.is_none_or(|f| from.starts_with(f)) &&
+            from_rank.is_none_or(|r| from.ends_with(r))
+        }).collect()
+      }
+    };
+
+    match candidates.len() {
+      1 => Ok(candidates[0]),
+      0 => Err("Move is illegal in this position"),
+      _ => Err("Move is ambiguous"),
+    }
+  }
+
+  pub fn to_san(m: &Move, game: &mut Game) -> Result<String, &'static str> {
+    if !game.state.is_legal_move(m) {
+      return Err("Move is illegal in this position");
+    }
+
+    let mut ret = String::new();
+
+    if let Some(c) = m.castling() {
+      ret.push_str(match c {
+        CastleType::Kingside => "O-O",
+        CastleType::Queenside => "O-O-O",
+      });
+    } else {
+      let from = match game.state.shift_to_algebraic(m.from) {
+        Some(x) => x,
+        None => return Err("Not a valid move"),
+      };
+
+      let to = match game.state.shift_to_algebraic(m.to) {
+        Some(x) => x,
+        None => return Err("Not a valid move"),
+      };
+
+      let capture = m.ep || game.state.relative_board.get_piece(m.to as i32).is_some();
+
+      if m.piece == Pieces::Pawn {
+        if capture {
+          ret.push_str(&from[0..1]);
+        }
+      } else {
+        ret.push(piece_to_san(m.piece));
+
+        // other pieces of the same kind that can reach the target
+        let others: Vec<Move> = game.legal_moves().into_iter()
+          .filter(|o| o.piece == m.piece && o.to == m.to && o.from != m.from)
+          .collect();
+
+        if !others.is_empty() {
+          if others.iter().all(|o| o.from % 8 != m.from % 8) {
+            ret.push_str(&from[0..1]);
+          } else if others.iter().all(|o| o.from / 8 != m.from / 8) {
+            ret.push_str(&from[1..2]);
+          } else {
+            ret.push_str(&from);
+          }
+        }
+      }
+
+      if capture {
+        ret.push('x');
+      }
+      ret.push_str(&to);
+
+      if let Some(p) = m.promotion {
+        ret.push('=');
+        ret.push(piece_to_san(p));
+      }
+    }
+
+    game.makemove(m)?;
+    if game.state.is_check(game.get_player()) {
+      if game.legal_moves().is_empty() {
+        ret.push('#');
+      } else {
+        ret.push('+');
+      }
+    }
+    game.undo_move();
+
+    Ok(ret)
+  }
+
   pub fn castling(&self) -> Option<CastleType> {
     if self.piece == Pieces::King {
       if self.from == 3 && self.to == 1 {
@@ -103,87 +271,31 @@ impl Move {
   }
 }
 
-pub enum Dir {
-  U,
-  D,
-  R,
-  L
+fn piece_from_san(c: char) -> Option<Pieces> {
+  match c {
+    'N' => Some(Pieces::Knight),
+    'B' => Some(Pieces::Bishop),
+    'R' => Some(Pieces::Rook),
+    'Q' => Some(Pieces::Queen),
+    'K' => Some(Pieces::King),
+    _ => None,
+  }
 }
 
-#[derive(PartialEq)]
-pub enum DiagDir {
-  NE,
-  NW,
-  SE,
-  SW
+fn piece_to_san(piece: Pieces) -> char {
+  match piece {
+    Pieces::Pawn => 'P',
+    Pieces::Knight => 'N',
+    Pieces::Bishop => 'B',
+    Pieces::Rook => 'R',
+    Pieces::Queen => 'Q',
+    Pieces::King => 'K',
+  }
 }
 
-
 pub struct MoveGen;
 
 impl MoveGen {
-  fn ray_mask(from: u32, blocker_mask: u64, direction: Dir) -> u64 {
-    let mut ret = 0;
-    let st_size: i32 = match direction {
-      Dir::U => 8,
-      Dir::D => -8,
-      Dir::L => -1,
-      Dir::R => 1,
-    };
-
-    let start_rank: i32 = (from as i32) / 8;
-    let start_file: i32 = (from as i32) % 8;
-
-    let mut sq = (from as i32) + st_size;
-    while sq < 64 && sq >= 0 && (sq >> 3 == start_rank || sq & 7 == start_file)  {
-      let to_mask = 1u64 << sq;
-
-      //if to_mask & blocker_mask != 0 {break;}
-
-      ret |= to_mask;
-      sq += st_size;
-
-      if to_mask & blocker_mask != 0 {
-        //ret |= to_mask;
-        break;
-      }
-    }
-
-    ret
-  }
-
-  fn diag_ray_mask(from: u32, blocker_mask: u64, direction: DiagDir) -> u64 {
-    let mut ret = 0;
-
-    let st_size: i32 = match direction {
-      DiagDir::NE => 9,
-      DiagDir::NW => 7,
-      DiagDir::SE => -7,
-      DiagDir::SW => -9,
-    };
-
-    let mut sq:i32 = from as i32 + st_size;
-
-
-    // ugly condition but it works
-    while sq < 64 && sq >= 0 && ((sq as u32 & 7) > (from & 7) && (direction == DiagDir::NE || direction == DiagDir::SE) ||
-                                 (sq as u32 & 7) < (from & 7) && (direction == DiagDir::NW || direction == DiagDir::SW) )  {
-      let to_mask = 1u64 << sq;
-
-      //if to_mask & blocker_mask != 0 {break;}
-
-      ret |= to_mask;
-      sq += st_size;
-
-      if to_mask & blocker_mask != 0 {
-        //ret |= to_mask;
-        break;
-      }
-    }
-
-    ret
-  }
-
   // these functions will also be used for checking if check or checkmate has occured 
   pub fn get_all_attacks(board: &Board, player: Player) -> u64 {
     let free_mask = board.get_freesq_mask();
@@ -196,16 +308,13 @@ impl MoveGen {
     let queens = board.get_pieceboard(player, Pieces::Queen).bitboard;
     let king = board.get_pieceboard(player, Pieces::King).bitboard;
 
-    
 
-    let attacked = MoveGen::get_pawn_attacks(pawns) | 
-                  MoveGen::get_knight_attacks(knights) |
-                  MoveGen::get_rook_attacks(rooks, free_mask) |
-                  MoveGen::get_bishop_attacks(bishops, free_mask) |
-                  MoveGen::get_queen_attacks(queens, free_mask) |
-                  MoveGen::get_king_attacks(king);
-  
-    attacked 
+    MoveGen::get_pawn_attacks(pawns) |
+      MoveGen::get_knight_attacks(knights) |
+      MoveGen::get_rook_attacks(rooks, free_mask) |
+      MoveGen::get_bishop_attacks(bishops, free_mask) |
+      MoveGen::get_queen_attacks(queens, free_mask) |
+      MoveGen::get_king_attacks(king)
   }
 
   pub fn get_pawn_attacks(pawns: u64) -> u64 {
@@ -235,15 +344,7 @@ impl MoveGen {
 
     while rooks != 0 {
       let from_sq = rooks.trailing_zeros();
-
-      let ray_u = MoveGen::ray_mask(from_sq, !free_mask, Dir::U);
-      let ray_d = MoveGen::ray_mask(from_sq, !free_mask, Dir::D);
-      let ray_l = MoveGen::ray_mask(from_sq, !free_mask, Dir::L);
-      let ray_r = MoveGen::ray_mask(from_sq, !free_mask, Dir::R);
-
-      targets |= ray_u | ray_d | ray_l | ray_r; 
-
-
+      targets |= magic::rook_attacks(from_sq, !free_mask);
       rooks ^= 1u64 << from_sq;
     }
 
@@ -255,16 +356,7 @@ impl MoveGen {
 
     while bishops != 0 {
       let from_sq = bishops.trailing_zeros();
-
-      let ray_ne = MoveGen::diag_ray_mask(from_sq, !free_mask, DiagDir::NE);
-      let ray_nw = MoveGen::diag_ray_mask(from_sq, !free_mask, DiagDir::NW);
-      let ray_se = MoveGen::diag_ray_mask(from_sq, !free_mask, DiagDir::SE);
-      let ray_sw = MoveGen::diag_ray_mask(from_sq, !free_mask, DiagDir::SW);
-
-
-      targets |= ray_ne | ray_nw | ray_se | ray_sw; 
-
-
+      targets |= magic::bishop_attacks(from_sq, !free_mask);
       bishops ^= 1u64 << from_sq;
     }
 
@@ -276,15 +368,11 @@ impl MoveGen {
 
     while queens != 0 {
       let from_sq = queens.trailing_zeros();
-      let queen = 1u64 << from_sq;
-
-      targets |= MoveGen::get_rook_attacks(queen, free_mask) | MoveGen::get_bishop_attacks(queen, free_mask);
-
+      targets |= magic::rook_attacks(from_sq, !free_mask) | magic::bishop_attacks(from_sq, !free_mask);
       queens ^= 1u64 << from_sq;
     }
 
     targets
-
   }
 
   pub fn get_king_attacks(king: u64) -> u64 {
@@ -301,14 +389,6 @@ impl MoveGen {
 
     let pawns = board.get_pieceboard(player, Pieces::Pawn);
     let free_mask = board.get_freesq_mask();
-  
-
-    let opp = match player {
-      Player::White => Player::Black,
-      Player::Black => Player::White,
-    };
-
-    let opp_piece_mask = board.get_player_mask(opp);
 
 
     // single pushes
@@ -320,6 +400,16 @@ impl MoveGen {
     let double_push = ((pawns.bitboard & 0xFF00u64) << 16) & free_mask & (free_mask << 8);
     moves.extend(MoveGen::collect_pawn_moves(double_push, 16));
 
+    moves.extend(MoveGen::pawn_captures(board, player, ep));
+
+    moves
+  }
+
+  fn pawn_captures(board: &Board, player: Player, ep: Option<u32>) -> Vec<Move> {
+    let mut moves: Vec<Move> = vec![];
+
+    let pawns = board.get_pieceboard(player, Pieces::Pawn);
+    let opp_piece_mask = board.get_player_mask(player.other());
 
     // capture
     let not_h_file = 0xfefefefefefefefeu64;
@@ -364,7 +454,7 @@ impl MoveGen {
 
       // compute targets
       let mut targets = MoveGen::get_knight_attacks(mask);
-      targets = targets & !board.get_player_mask(player);
+      targets &= !board.get_player_mask(player);
 
       moves.extend(MoveGen::collect_moves(from_sq, targets, Pieces::Knight));
     }
@@ -463,7 +553,7 @@ impl MoveGen {
       let to_sq = targets.trailing_zeros();
       targets ^= 1u64 << to_sq;
 
-      moves.push(Move{piece: piece, from: from, to: to_sq, promotion: None, ep: false});
+      moves.push(Move{piece, from, to: to_sq, promotion: None, ep: false});
     }
 
     moves
@@ -476,7 +566,7 @@ impl MoveGen {
       targets ^= 1u64 << to_sq;
       let from_sq = to_sq - shift;
 
-      if to_sq <= 63 && to_sq >= 63 - 7 {
+      if (63 - 7..=63).contains(&to_sq) {
         for p in [Pieces::Knight, Pieces::Bishop, Pieces::Rook, Pieces::Queen] {
           moves.push(Move{piece: Pieces::Pawn, from: from_sq, to: to_sq, promotion: Some(p), ep: false});
         } 
@@ -522,4 +612,229 @@ impl MoveGen {
 
     moves
   }
+
+  // pseudo legal captures (en passant included) and promotions, the moves of the quiescence search
+  pub fn captures(game: &GameState) -> Vec<Move> {
+    let board = game.get_relative_board();
+    let player = game.get_player();
+
+    let free_mask = board.get_freesq_mask();
+    let opp_piece_mask = board.get_player_mask(player.other());
+
+    let mut moves = MoveGen::pawn_captures(&board, player, game.get_ep());
+
+    // pushes to the last rank
+    let pawns = board.get_pieceboard(player, Pieces::Pawn).bitboard;
+    let promotions = (pawns << 8) & free_mask & 0xFF00000000000000u64;
+    moves.extend(MoveGen::collect_pawn_moves(promotions, 8));
+
+    for piece in [Pieces::Knight, Pieces::Rook, Pieces::Bishop, Pieces::Queen, Pieces::King] {
+      let mut pieces = board.get_pieceboard(player, piece).bitboard;
+
+      while pieces != 0 {
+        let from_sq = pieces.trailing_zeros();
+        let mask = 1u64 << from_sq;
+
+        let attacks = match piece {
+          Pieces::Knight => MoveGen::get_knight_attacks(mask),
+          Pieces::Rook => MoveGen::get_rook_attacks(mask, free_mask),
+          Pieces::Bishop => MoveGen::get_bishop_attacks(mask, free_mask),
+          Pieces::Queen => MoveGen::get_queen_attacks(mask, free_mask),
+          _ => MoveGen::get_king_attacks(mask),
+        };
+
+        moves.extend(MoveGen::collect_moves(from_sq, attacks & opp_piece_mask, piece));
+
+        pieces ^= mask;
+      }
+    }
+
+    moves
+  }
+
+  // the squares strictly between two squares on the same rank, file or diagonal, 0 otherwise
+  fn between(a: u32, b: u32) -> u64 {
+    let (a_bit, b_bit) = (1u64 << a, 1u64 << b);
+
+    if magic::rook_attacks(a, 0) & b_bit != 0 {
+      magic::rook_attacks(a, b_bit) & magic::rook_attacks(b, a_bit)
+    } else if magic::bishop_attacks(a, 0) & b_bit != 0 {
+      magic::bishop_attacks(a, b_bit) & magic::bishop_attacks(b, a_bit)
+    } else {
+      0
+    }
+  }
+
+  // pieces of the opponent attacking `sq` on the relative board, only pieces on `occupied` count
+  fn attackers(board: &Board, opp: Player, sq: u32, occupied: u64) -> u64 {
+    let piece = |p: Pieces| board.get_pieceboard(opp, p).bitboard;
+    let queens = piece(Pieces::Queen);
+
+    let attackers = MoveGen::get_pawn_attacks(1u64 << sq) & piece(Pieces::Pawn) |
+      KNIGHT_MOVES_LOOKUP[sq as usize] & piece(Pieces::Knight) |
+      magic::rook_attacks(sq, occupied) & (piece(Pieces::Rook) | queens) |
+      magic::bishop_attacks(sq, occupied) & (piece(Pieces::Bishop) | queens) |
+      KING_MOVES_LOOKUP[sq as usize] & piece(Pieces::King);
+
+    attackers & occupied
+  }
+
+  // every square the opponent attacks on the relative board, its pawns move down
+  fn opp_attacks(board: &Board, opp: Player, occupied: u64) -> u64 {
+    let not_h_file = 0xfefefefefefefefeu64;
+    let not_a_file = 0x7f7f7f7f7f7f7f7fu64;
+    let pawns = board.get_pieceboard(opp, Pieces::Pawn).bitboard;
+    let king = board.get_pieceboard(opp, Pieces::King).bitboard;
+
+    ((pawns & not_h_file) >> 9) | ((pawns & not_a_file) >> 7) |
+      MoveGen::get_knight_attacks(board.get_pieceboard(opp, Pieces::Knight).bitboard) |
+      MoveGen::get_rook_attacks(board.get_pieceboard(opp, Pieces::Rook).bitboard, !occupied) |
+      MoveGen::get_bishop_attacks(board.get_pieceboard(opp, Pieces::Bishop).bitboard, !occupied) |
+      MoveGen::get_queen_attacks(board.get_pieceboard(opp, Pieces::Queen).bitboard, !occupied) |
+      if king != 0 { MoveGen::get_king_attacks(king) } else { 0 }
+  }
+
+  // whether a pawn of the side to move can take en passant without leaving its king in check, the
+  // same test `legal` does for the capture without generating the other moves
+  pub fn can_take_en_passant(game: &GameState) -> bool {
+    let Some(ep) = game.get_ep() else {
+      return false;
+    };
+
+    let board = game.get_relative_board();
+    let player = game.get_player();
+    let not_h_file = 0xfefefefefefefefeu64;
+    let not_a_file = 0x7f7f7f7f7f7f7f7fu64;
+    let captured = 1u64 << ep;
+    let mut pawns = (((captured & not_a_file) << 1) | ((captured & not_h_file) >> 1)) & board.get_pieceboard(player, Pieces::Pawn).bitboard;
+
+    let king = board.get_pieceboard(player, Pieces::King).bitboard;
+    if king == 0 {
+      return pawns != 0;
+    }
+
+    let occupied = !board.get_freesq_mask();
+    while pawns != 0 {
+      let from = pawns & pawns.wrapping_neg();
+      pawns ^= from;
+
+      let after = (occupied ^ from ^ captured) | (captured << 8);
+      if MoveGen::attackers(&board, player.other(), king.trailing_zeros(), after) == 0 {
+        return true;
+      }
+    }
+
+    false
+  }
+
+  // only legal moves, in the same order as `pseudo_legal`. checkers, pinned pieces and the squares
+  // that stop a check are computed once instead of playing every move and looking for a check
+  pub fn legal(game: &GameState) -> Vec<Move> {
+    let board = game.get_relative_board();
+    let player = game.get_player();
+    let opp = player.other();
+    let castling_r = game.get_castling();
+
+    let king = board.get_pieceboard(player, Pieces::King).bitboard;
+    if king == 0 {
+      return MoveGen::pseudo_legal(game);
+    }
+
+    let king_sq = king.trailing_zeros();
+    let own = board.get_player_mask(player);
+    let occupied = !board.get_freesq_mask();
+    let checkers = MoveGen::attackers(&board, opp, king_sq, occupied);
+
+    // the king may not step back along the ray of a slider checking it
+    let attacked = MoveGen::opp_attacks(&board, opp, occupied ^ king);
+    let mut moves = MoveGen::collect_moves(king_sq, KING_MOVES_LOOKUP[king_sq as usize] & !own & !attacked, Pieces::King);
+
+    // in double check only the king can move
+    if checkers.count_ones() > 1 {
+      return moves;
+    }
+
+    // capture the checker or block its ray
+    let evasions = match checkers {
+      0 => !0u64,
+      c => c | MoveGen::between(king_sq, c.trailing_zeros()),
+    };
+
+    // a pinned piece stays on the line between the king and the pinning slider
+    let opp_piece = |p: Pieces| board.get_pieceboard(opp, p).bitboard;
+    let mut snipers = magic::rook_attacks(king_sq, 0) & (opp_piece(Pieces::Rook) | opp_piece(Pieces::Queen)) |
+      magic::bishop_attacks(king_sq, 0) & (opp_piece(Pieces::Bishop) | opp_piece(Pieces::Queen));
+    let mut pin_rays = [!0u64; 64];
+
+    while snipers != 0 {
+      let sniper = snipers.trailing_zeros();
+      snipers &= snipers - 1;
+
+      let ray = MoveGen::between(king_sq, sniper);
+      let blockers = ray & occupied;
+      if blockers.count_ones() == 1 && blockers & own != 0 {
+        pin_rays[blockers.trailing_zeros() as usize] = ray | (1u64 << sniper);
+      }
+    }
+
+    let allowed = |m: &Move| (1u64 << m.to) & evasions & pin_rays[m.from as usize] != 0;
+
+    let mut legal: Vec<Move> = vec![];
+    for m in MoveGen::pawn_moves(&board, player, game.get_ep()) {
+      if !m.ep {
+        if allowed(&m) {
+          legal.push(m);
+        }
+        continue;
+      }
+
+      // the captured pawn leaves the board as well, which can uncover a slider on the king's rank
+      let captured = 1u64 << (m.to - 8);
+      let after = (occupied ^ (1u64 << m.from) ^ captured) | (1u64 << m.to);
+      if MoveGen::attackers(&board, opp, king_sq, after) == 0 {
+        legal.push(m);
+      }
+    }
+
+    for piece in [Pieces::Knight, Pieces::Rook, Pieces::Bishop, Pieces::Queen] {
+      let mut pieces = board.get_pieceboard(player, piece).bitboard;
+
+      while pieces != 0 {
+        let from_sq = pieces.trailing_zeros();
+        let mask = 1u64 << from_sq;
+
+        let attacks = match piece {
+          Pieces::Knight => MoveGen::get_knight_attacks(mask),
+          Pieces::Rook => MoveGen::get_rook_attacks(mask, !occupied),
+          Pieces::Bishop => MoveGen::get_bishop_attacks(mask, !occupied),
+          _ => MoveGen::get_queen_attacks(mask, !occupied),
+        };
+
+        let targets = attacks & !own & evasions & pin_rays[from_sq as usize];
+        legal.extend(MoveGen::collect_moves(from_sq, targets, piece));
+
+        pieces ^= mask;
+      }
+    }
+
+    // castling out of check or through an attacked square is not allowed
+    if checkers == 0 && king_sq == 3 {
+      let (kingside, queenside) = match player {
+        Player::White => (CASTLE_WHITE_KINGSIDE, CASTLE_WHITE_QUEENSIDE),
+        Player::Black => (CASTLE_BLACK_KINGSIDE, CASTLE_BLACK_QUEENSIDE),
+      };
+      let c_kingside = 1u64 << 2 | 1u64 << 1;
+      let c_queenside = 1u64 << 4 | 1u64 << 5 | 1u64 << 6;
+
+      if castling_r & kingside != 0 && c_kingside & (occupied | attacked) == 0 {
+        moves.push(Move{piece: Pieces::King, from: 3, to: 1, promotion: None, ep: false});
+      }
+      if castling_r & queenside != 0 && c_queenside & occupied == 0 && (1u64 << 4 | 1u64 << 5) & attacked == 0 {
+        moves.push(Move{piece: Pieces::King, from: 3, to: 5, promotion: None, ep: false});
+      }
+    }
+
+    legal.extend(moves);
+    legal
+  }
 }
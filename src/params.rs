@@ -0,0 +1,339 @@
+use std::fs;
+use std::io;
+
+// every weight of the evaluation as (middlegame, endgame) pairs unless noted otherwise, all values are
+// from the point of view of the side that owns the piece
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalParams {
+  // indexed like Pieces: pawn, rook, knight, bishop, queen, king
+  pub material: [(i32, i32); 6],
+  // written from white's point of view as seen on a diagram, the first entry is a8 and the last one is h1
+  pub pst_mg: [[i32; 64]; 6],
+  pub pst_eg: [[i32; 64]; 6],
+
+  // indexed by the rank as seen from the pawn's own side
+  pub passed: [(i32, i32); 8],
+  // on top of passed when nothing stands on the squares in front of the pawn
+  pub passed_free: [(i32, i32); 8],
+  pub supported: [(i32, i32); 8],
+  pub phalanx: [(i32, i32); 8],
+  pub doubled: (i32, i32),
+  pub isolated: (i32, i32),
+  pub backward: (i32, i32),
+  // for every island beyond the first one
+  pub island: (i32, i32),
+
+  // own pawns one and two ranks in front of the king, on its file and the files next to it
+  pub shield: [(i32, i32); 2],
+  // the closest enemy pawn one, two or three ranks in front of the king
+  pub storm: [(i32, i32); 3],
+  // files around the king without any pawn, or without a pawn of its own
+  pub open_file: (i32, i32),
+  pub semi_open_file: (i32, i32),
+  // every attacked square of the king zone, indexed like Pieces
+  pub king_attack: [(i32, i32); 6],
+  // percentages, not pairs: the share of the attack weight that counts for the number of attackers
+  pub king_attackers_scale: [i32; 8],
+
+  // per safe square above or below the usual number, indexed like Pieces
+  pub mobility: [(i32, i32); 6],
+  pub bishop_pair: (i32, i32),
+  pub rook_open_file: (i32, i32),
+  pub rook_semi_open_file: (i32, i32),
+  // only when the enemy king is on its back rank or there are enemy pawns to pick up
+  pub rook_on_seventh: (i32, i32),
+  // a knight on the 4th to 6th rank, supported by a pawn and out of reach of the enemy pawns
+  pub knight_outpost: (i32, i32),
+  // a rook in the corner next to a king that has not castled
+  pub trapped_rook: (i32, i32),
+  // a bishop on a7 or h7 that is shut in by a pawn on b6 or g6
+  pub trapped_bishop: (i32, i32),
+}
+
+pub static DEFAULT_PARAMS: EvalParams = EvalParams {
+  material: [(82, 94), (477, 512), (337, 281), (365, 297), (1025, 936), (0, 0)],
+  pst_mg: [
+    // pawn
+    [
+         0,   0,   0,   0,   0,   0,   0,   0,
+        98, 134,  61,  95,  68, 126,  34, -11,
+        -6,   7,  26,  31,  65,  56,  25, -20,
+       -14,  13,   6,  21,  23,  12,  17, -23,
+       -27,  -2,  -5,  12,  17,   6,  10, -25,
+       -26,  -4,  -4, -10,   3,   3,  33, -12,
+       -35,  -1, -20, -23, -15,  24,  38, -22,
+         0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    // rook
+    [
+        32,  42,  32,  51,  63,   9,  31,  43,
+        27,  32,  58,  62,  80,  67,  26,  44,
+        -5,  19,  26,  36,  17,  45,  61,  16,
+       -24, -11,   7,  26,  24,  35,  -8, -20,
+       -36, -26, -12,  -1,   9,  -7,   6, -23,
+       -45, -25, -16, -17,   3,   0,  -5, -33,
+       -44, -16, -20,  -9,  -1,  11,  -6, -71,
+       -19, -13,   1,  17,  16,   7, -37, -26,
+    ],
+    // knight
+    [
+      -167, -89, -34, -49,  61, -97, -15, -107,
+       -73, -41,  72,  36,  23,  62,   7,  -17,
+       -47,  60,  37,  65,  84, 129,  73,   44,
+        -9,  17,  19,  53,  37,  69,  18,   22,
+       -13,   4,  16,  13,  28,  19,  21,   -8,
+       -23,  -9,  12,  10,  19,  17,  25,  -16,
+       -29, -53, -12,  -3,  -1,  18, -14,  -19,
+      -105, -21, -58, -33, -17, -28, -19,  -23,
+    ],
+    // bishop
+    [
+       -29,   4, -82, -37, -25, -42,   7,  -8,
+       -26,  16, -18, -13,  30,  59,  18, -47,
+       -16,  37,  43,  40,  35,  50,  37,  -2,
+        -4,   5,  19,  50,  37,  37,   7,  -2,
+        -6,  13,  13,  26,  34,  12,  10,   4,
+         0,  15,  15,  15,  14,  27,  18,  10,
+         4,  15,  16,   0,   7,  21,  33,   1,
+       -33,  -3, -14, -21, -13, -12, -39, -21,
+    ],
+    // queen
+    [
+       -28,   0,  29,  12,  59,  44,  43,  45,
+       -24, -39,  -5,   1, -16,  57,  28,  54,
+       -13, -17,   7,   8,  29,  56,  47,  57,
+       -27, -27, -16, -16,  -1,  17,  -2,   1,
+        -9, -26,  -9, -10,  -2,  -4,   3,  -3,
+       -14,   2, -11,  -2,  -5,   2,  14,   5,
+       -35,  -8,  11,   2,   8,  15,  -3,   1,
+        -1, -18,  -9,  10, -15, -25, -31, -50,
+    ],
+    // king
+    [
+       -65,  23,  16, -15, -56, -34,   2,  13,
+        29,  -1, -20,  -7,  -8,  -4, -38, -29,
+        -9,  24,   2, -16, -20,   6,  22, -22,
+       -17, -20, -12, -27, -30, -25, -14, -36,
+       -49,  -1, -27, -39, -46, -44, -33, -51,
+       -14, -14, -22, -46, -44, -30, -15, -27,
+         1,   7,  -8, -64, -43, -16,   9,   8,
+       -15,  36,  12, -54,   8, -28,  24,  14,
+    ],
+  ],
+  pst_eg: [
+    // pawn
+    [
+         0,   0,   0,   0,   0,   0,   0,   0,
+       178, 173, 158, 134, 147, 132, 165, 187,
+        94, 100,  85,  67,  56,  53,  82,  84,
+        32,  24,  13,   5,  -2,   4,  17,  17,
+        13,   9,  -3,  -7,  -7,  -8,   3,  -1,
+         4,   7,  -6,   1,   0,  -5,  -1,  -8,
+        13,   8,   8,  10,  13,   0,   2,  -7,
+         0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    // rook
+    [
+        13,  10,  18,  15,  12,  12,   8,   5,
+        11,  13,  13,  11,  -3,   3,   8,   3,
+         7,   7,   7,   5,   4,  -3,  -5,  -3,
+         4,   3,  13,   1,   2,   1,  -1,   2,
+         3,   5,   8,   4,  -5,  -6,  -8, -11,
+        -4,   0,  -5,  -1,  -7, -12,  -8, -16,
+        -6,  -6,   0,   2,  -9,  -9, -11,  -3,
+        -9,   2,   3,  -1,  -5, -13,   4, -20,
+    ],
+    // knight
+    [
+       -58, -38, -13, -28, -31, -27, -63, -99,
+       -25,  -8, -25,  -2,  -9, -25, -24, -52,
+       -24, -20,  10,   9,  -1,  -9, -19, -41,
+       -17,   3,  22,  22,  22,  11,   8, -18,
+       -18,  -6,  16,  25,  16,  17,   4, -18,
+       -23,  -3,  -1,  15,  10,  -3, -20, -22,
+       -42, -20, -10,  -5,  -2, -20, -23, -44,
+       -29, -51, -23, -15, -22, -18, -50, -64,
+    ],
+    // bishop
+    [
+       -14, -21, -11,  -8,  -7,  -9, -17, -24,
+        -8,  -4,   7, -12,  -3, -13,  -4, -14,
+         2,  -8,   0,  -1,  -2,   6,   0,   4,
+        -3,   9,  12,   9,  14,  10,   3,   2,
+        -6,   3,  13,  19,   7,  10,  -3,  -9,
+       -12,  -3,   8,  10,  13,   3,  -7, -15,
+       -14, -18,  -7,  -1,   4,  -9, -15, -27,
+       -23,  -9, -23,  -5,  -9, -16,  -5, -17,
+    ],
+    // queen
+    [
+        -9,  22,  22,  27,  27,  19,  10,  20,
+       -17,  20,  32,  41,  58,  25,  30,   0,
+       -20,   6,   9,  49,  47,  35,  19,   9,
+         3,  22,  24,  45,  57,  40,  57,  36,
+       -18,  28,  19,  47,  31,  34,  39,  23,
+       -16, -27,  15,   6,   9,  17,  10,   5,
+       -22, -23, -30, -16, -16, -23, -36, -32,
+       -33, -28, -22, -43,  -5, -32, -20, -41,
+    ],
+    // king
+    [
+       -74, -35, -18, -18, -11,  15,   4, -17,
+       -12,  17,  14,  17,  17,  38,  23,  11,
+        10,  17,  23,  15,  20,  45,  44,  13,
+        -8,  22,  24,  27,  26,  33,  26,   3,
+       -18,  -4,  21,  24,  27,  23,   9, -11,
+       -19,  -3,  11,  21,  23,  16,   7,  -9,
+       -27, -11,   4,  13,  14,   4,  -5, -17,
+       -53, -34, -21, -11, -28, -14, -24, -43,
+    ],
+  ],
+
+  passed: [(0, 0), (5, 10), (10, 15), (15, 25), (25, 45), (45, 75), (70, 120), (0, 0)],
+  passed_free: [(0, 0), (0, 0), (0, 5), (5, 10), (10, 25), (20, 45), (35, 70), (0, 0)],
+  supported: [(0, 0), (0, 0), (5, 3), (8, 5), (12, 8), (20, 15), (30, 25), (0, 0)],
+  phalanx: [(0, 0), (2, 1), (4, 3), (6, 4), (10, 8), (18, 15), (30, 25), (0, 0)],
+  doubled: (-10, -20),
+  isolated: (-8, -12),
+  backward: (-8, -10),
+  island: (-5, -10),
+
+  shield: [(15, 0), (8, 0)],
+  storm: [(-4, 0), (-18, 0), (-10, 0)],
+  open_file: (-25, 0),
+  semi_open_file: (-12, 0),
+  king_attack: [(0, 0), (40, 0), (20, 0), (20, 0), (80, 0), (0, 0)],
+  king_attackers_scale: [0, 0, 50, 75, 88, 94, 97, 99],
+
+  mobility: [(0, 0), (3, 5), (4, 4), (5, 5), (1, 2), (0, 0)],
+  bishop_pair: (30, 50),
+  rook_open_file: (25, 10),
+  rook_semi_open_file: (12, 6),
+  rook_on_seventh: (20, 30),
+  knight_outpost: (20, 10),
+  trapped_rook: (-45, -5),
+  trapped_bishop: (-100, -80),
+};
+
+const PST_MG_NAMES: [&str; 6] = ["pst_mg_pawn", "pst_mg_rook", "pst_mg_knight", "pst_mg_bishop", "pst_mg_queen", "pst_mg_king"];
+const PST_EG_NAMES: [&str; 6] = ["pst_eg_pawn", "pst_eg_rook", "pst_eg_knight", "pst_eg_bishop", "pst_eg_queen", "pst_eg_king"];
+
+impl Default for EvalParams {
+  fn default() -> Self {
+    DEFAULT_PARAMS.clone()
+  }
+}
+
+// pairs are handed out flat as mg, eg, mg, eg, ... and written back afterwards
+fn visit_pairs(name: &'static str, pairs: &mut [(i32, i32)], f: &mut dyn FnMut(&'static str, &mut [i32])) {
+  let mut values: Vec<i32> = pairs.iter().flat_map(|&(mg, eg)| [mg, eg]).collect();
+  f(name, &mut values);
+
+  for (i, pair) in pairs.iter_mut().enumerate() {
+    *pair = (values[2 * i], values[2 * i + 1]);
+  }
+}
+
+impl EvalParams {
+  // calls `f` with the name and the values of every parameter, always in the same order
+  fn visit(&mut self, f: &mut dyn FnMut(&'static str, &mut [i32])) {
+    visit_pairs("material", &mut self.material, f);
+    for (name, table) in PST_MG_NAMES.iter().zip(self.pst_mg.iter_mut()) {
+      f(name, table);
+    }
+    for (name, table) in PST_EG_NAMES.iter().zip(self.pst_eg.iter_mut()) {
+      f(name, table);
+    }
+
+    visit_pairs("passed", &mut self.passed, f);
+    visit_pairs("passed_free", &mut self.passed_free, f);
+    visit_pairs("supported", &mut self.supported, f);
+    visit_pairs("phalanx", &mut self.phalanx, f);
+    visit_pairs("doubled", std::slice::from_mut(&mut self.doubled), f);
+    visit_pairs("isolated", std::slice::from_mut(&mut self.isolated), f);
+    visit_pairs("backward", std::slice::from_mut(&mut self.backward), f);
+    visit_pairs("island", std::slice::from_mut(&mut self.island), f);
+
+    visit_pairs("shield", &mut self.shield, f);
+    visit_pairs("storm", &mut self.storm, f);
+    visit_pairs("open_file", std::slice::from_mut(&mut self.open_file), f);
+    visit_pairs("semi_open_file", std::slice::from_mut(&mut self.semi_open_file), f);
+    visit_pairs("king_attack", &mut self.king_attack, f);
+    f("king_attackers_scale", &mut self.king_attackers_scale);
+
+    visit_pairs("mobility", &mut self.mobility, f);
+    visit_pairs("bishop_pair", std::slice::from_mut(&mut self.bishop_pair), f);
+    visit_pairs("rook_open_file", std::slice::from_mut(&mut self.rook_open_file), f);
+    visit_pairs("rook_semi_open_file", std::slice::from_mut(&mut self.rook_semi_open_file), f);
+    visit_pairs("rook_on_seventh", std::slice::from_mut(&mut self.rook_on_seventh), f);
+    visit_pairs("knight_outpost", std::slice::from_mut(&mut self.knight_outpost), f);
+    visit_pairs("trapped_rook", std::slice::from_mut(&mut self.trapped_rook), f);
+    visit_pairs("trapped_bishop", std::slice::from_mut(&mut self.trapped_bishop), f);
+  }
+
+  // every value in the order of the parameter file, pairs are flattened to mg, eg
+  pub fn values(&self) -> Vec<i32> {
+    let mut ret = vec![];
+    self.clone().visit(&mut |_, values| ret.extend_from_slice(values));
+    ret
+  }
+
+  // the counterpart of values, `values` must have exactly one entry per value
+  pub fn set_values(&mut self, values: &[i32]) {
+    let mut rest = values;
+    self.visit(&mut |_, v| {
+      let (head, tail) = rest.split_at(v.len());
+      v.copy_from_slice(head);
+      rest = tail;
+    });
+    assert!(rest.is_empty(), "Too many values for the eval parameters");
+  }
+
+  // one line per parameter: the name followed by its values, pairs are written as mg eg
+  pub fn to_text(&self) -> String {
+    let mut ret = String::from("# rustchess evaluation parameters, pairs are written as middlegame endgame\n");
+
+    self.clone().visit(&mut |name, values| {
+      let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+      ret.push_str(&format!("{} {}\n", name, values.join(" ")));
+    });
+
+    ret
+  }
+
+  // parameters that are left out keep their default value, empty lines and lines starting with # are skipped
+  pub fn from_text(text: &str) -> Result<Self, &'static str> {
+    let mut ret = EvalParams::default();
+
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+      let mut tokens = line.split_whitespace();
+      let name = tokens.next().unwrap_or_default();
+      let values = tokens.map(|t| t.parse::<i32>()).collect::<Result<Vec<i32>, _>>()
+        .map_err(|_| "Eval parameters must be integers")?;
+
+      let mut res = Err("Unknown eval parameter");
+      ret.visit(&mut |n, v| {
+        if n == name {
+          res = if v.len() == values.len() {
+            v.copy_from_slice(&values);
+            Ok(())
+          } else {
+            Err("Wrong number of values for an eval parameter")
+          };
+        }
+      });
+      res?;
+    }
+
+    Ok(ret)
+  }
+
+  pub fn load(path: &str) -> Result<Self, &'static str> {
+    let text = fs::read_to_string(path).map_err(|_| "Could not read the eval file")?;
+    EvalParams::from_text(&text)
+  }
+
+  pub fn save(&self, path: &str) -> io::Result<()> {
+    fs::write(path, self.to_text())
+  }
+}
@@ -0,0 +1,201 @@
+use crate::board::{Board, Player, Pieces};
+use crate::params::{EvalParams};
+
+// squares are absolute: shift 0 is h1, shift 7 is a1 and shift 63 is a8
+const FILE_H: u64 = 0x0101_0101_0101_0101;
+const FILE_A: u64 = FILE_H << 7;
+
+pub const PAWN_TABLE_ENTRIES: usize = 1 << 14;
+
+// pawn structure of both sides, only depends on the pawns
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct PawnEntry {
+  pub key: u64,
+  // (middlegame, endgame) indexed by player, each from its own point of view
+  pub score: [(i32, i32); 2],
+  // indexed by player, terms that need the other pieces (like a free path) are added by the caller
+  pub passed: [u64; 2],
+}
+
+pub struct PawnTable {
+  entries: Vec<Option<PawnEntry>>,
+}
+
+impl Default for PawnTable {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl PawnTable {
+  pub fn new() -> Self {
+    PawnTable {entries: vec![None; PAWN_TABLE_ENTRIES]}
+  }
+
+  pub fn clear(&mut self) {
+    self.entries.fill(None);
+  }
+
+  #[inline]
+  fn index(&self, key: u64) -> usize {
+    (key as usize) & (self.entries.len() - 1)
+  }
+
+  pub fn probe(&self, key: u64) -> Option<PawnEntry> {
+    match self.entries[self.index(key)] {
+      Some(e) if e.key == key => Some(e),
+      _ => None,
+    }
+  }
+
+  // the board must be absolute and `key` its pawn hash
+  pub fn get(&mut self, key: u64, board: &Board, params: &EvalParams) -> PawnEntry {
+    if let Some(e) = self.probe(key) {
+      return e;
+    }
+
+    let entry = PawnEntry {key, ..evaluate(board, params)};
+    let idx = self.index(key);
+    self.entries[idx] = Some(entry);
+    entry
+  }
+}
+
+#[inline]
+pub fn file_mask(shift: u32) -> u64 {
+  FILE_H << (shift & 7)
+}
+
+#[inline]
+pub fn adjacent_files(shift: u32) -> u64 {
+  let file = file_mask(shift);
+  ((file & !FILE_A) << 1) | ((file & !FILE_H) >> 1)
+}
+
+// every square on the ranks in front of `shift` as seen by `player`
+#[inline]
+pub fn forward_ranks(player: Player, shift: u32) -> u64 {
+  let rank = shift / 8;
+  match player {
+    Player::White if rank == 7 => 0,
+    Player::White => !0u64 << (8 * (rank + 1)),
+    Player::Black => (1u64 << (8 * rank)) - 1,
+  }
+}
+
+// the rank counted from the player's own side, 0 is the first rank
+#[inline]
+pub fn relative_rank(player: Player, shift: u32) -> usize {
+  match player {
+    Player::White => (shift / 8) as usize,
+    Player::Black => 7 - (shift / 8) as usize,
+  }
+}
+
+pub fn pawn_attacks(pawns: u64, player: Player) -> u64 {
+  match player {
+    Player::White => ((pawns & !FILE_A) << 9) | ((pawns & !FILE_H) << 7),
+    Player::Black => ((pawns & !FILE_A) >> 7) | ((pawns & !FILE_H) >> 9),
+  }
+}
+
+fn islands(pawns: u64) -> i32 {
+  let mut files = 0u8;
+  for f in 0..8 {
+    if pawns & (FILE_H << f) != 0 {
+      files |= 1 << f;
+    }
+  }
+
+  // every island starts with a file that has no pawns on its right
+  (files & !(files << 1)).count_ones() as i32
+}
+
+fn add(score: &mut (i32, i32), w: (i32, i32), n: i32) {
+  score.0 += n * w.0;
+  score.1 += n * w.1;
+}
+
+// passed, doubled, isolated, backward, supported and phalanx pawns and pawn islands of both sides
+pub fn evaluate(board: &Board, params: &EvalParams) -> PawnEntry {
+  let mut entry = PawnEntry::default();
+
+  for player in [Player::White, Player::Black] {
+    let score = &mut entry.score[player as usize];
+    let own = board.get_pieceboard(player, Pieces::Pawn).bitboard;
+    let opp = board.get_pieceboard(player.other(), Pieces::Pawn).bitboard;
+    let own_attacks = pawn_attacks(own, player);
+    let opp_attacks = pawn_attacks(opp, player.other());
+
+    for f in 0..8 {
+      let n = (own & (FILE_H << f)).count_ones() as i32;
+      if n > 1 {
+        add(score, params.doubled, n - 1);
+      }
+    }
+
+    let n = islands(own);
+    if n > 1 {
+      add(score, params.island, n - 1);
+    }
+
+    let mut pawns = own;
+    while pawns != 0 {
+      let shift = pawns.trailing_zeros();
+      let bit = 1u64 << shift;
+      let rank = relative_rank(player, shift);
+      let front = forward_ranks(player, shift);
+      let neighbours = own & adjacent_files(shift);
+      pawns &= pawns - 1;
+
+      // only the front pawn of a doubled pair counts as passed
+      if opp & front & (file_mask(shift) | adjacent_files(shift)) == 0 && own & front & file_mask(shift) == 0 {
+        entry.passed[player as usize] |= bit;
+        add(score, params.passed[rank], 1);
+      }
+
+      let supported = own_attacks & bit != 0;
+      let phalanx = neighbours & (0xff << (shift & !7)) != 0;
+      if supported {
+        add(score, params.supported[rank], 1);
+      }
+      if phalanx {
+        add(score, params.phalanx[rank], 1);
+      }
+
+      if neighbours == 0 {
+        add(score, params.isolated, 1);
+        continue;
+      }
+
+      // every neighbour has already passed it and an enemy pawn controls the square in front
+      let stop = match player {
+        Player::White => bit << 8,
+        Player::Black => bit >> 8,
+      };
+      if !supported && !phalanx && neighbours & !front == 0 && opp_attacks & stop != 0 {
+        add(score, params.backward, 1);
+      }
+    }
+  }
+
+  entry
+}
+
+// passed pawns whose way to promotion is not blocked, cannot be cached since it depends on every piece
+pub fn free_passers(entry: &PawnEntry, board: &Board, player: Player, params: &EvalParams) -> (i32, i32) {
+  let occupied = !board.get_freesq_mask();
+  let mut score = (0, 0);
+
+  let mut passed = entry.passed[player as usize];
+  while passed != 0 {
+    let shift = passed.trailing_zeros();
+    passed &= passed - 1;
+
+    if forward_ranks(player, shift) & file_mask(shift) & occupied == 0 {
+      add(&mut score, params.passed_free[relative_rank(player, shift)], 1);
+    }
+  }
+
+  score
+}
@@ -11,17 +11,10 @@ pub fn perft(game: &mut Game, depth: usize) -> u32 {
   let mut nodes = 0;   
 
   for m in moves.iter() {
-    let n = match game.makemove(m) {
-      Ok(_) => {
-        let r = debug_perft(game, depth-1, false);
-        r
-      }
-      Err(_)  => {
-        println!("Warning: None returned by makemove (probably illegal move)");
-        0
-      },
-    };
-    
+    // the moves are legal already, no need to look for a check again
+    game.do_move(m);
+    let n = perft(game, depth-1);
+
     nodes += n;
     game.undo_move();
   }
@@ -29,40 +22,20 @@ pub fn perft(game: &mut Game, depth: usize) -> u32 {
   nodes
 }
 
-pub fn debug_perft(game: &mut Game, depth: usize, print: bool) -> u32 {
+// the nodes below every legal move, for comparing a perft with another engine move by move
+pub fn debug_perft(game: &mut Game, depth: usize) -> Vec<(Move, u32)> {
   if depth == 0 {
-    return 1;
+    return vec![];
   }
 
   let moves = game.legal_moves();
-
-  let mut nodes = 0;   
+  let mut counts = vec![];
 
   for m in moves.iter() {
-    let n = match game.makemove(m) {
-      Ok(_) => {
-        let r = debug_perft(game, depth-1, false);
-        r
-      }
-      Err(_) => {
-        println!("Warning: None returned by makemove (probably illegal move)");
-        0
-      },
-    };
-
-    nodes += n;
+    game.do_move(m);
+    counts.push((*m, perft(game, depth-1)));
     game.undo_move();
-
-    let lan = match Move::to_lan(m, &game.state) {
-      Ok(x) => x,
-      _ => String::from("Error decoding lan"),
-    };
-  
-    if print {
-      println!("{}: {}", lan, n);
-    }
   }
 
-  nodes
+  counts
 }
-
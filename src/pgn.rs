@@ -0,0 +1,454 @@
+use std::io::BufRead;
+
+use crate::board::{Player};
+use crate::game::{Game, GameResult, GameState};
+use crate::movegen::{Move};
+
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+  ("Event", "?"),
+  ("Site", "?"),
+  ("Date", "????.??.??"),
+  ("Round", "?"),
+  ("White", "?"),
+  ("Black", "?"),
+  ("Result", "*"),
+];
+
+const MAX_LINE_LEN: usize = 79;
+
+// moves played through makemove are recorded so that to_pgn can write them back, moves made directly
+// on game are not part of the movetext
+pub struct PgnGame {
+  pub tags: Vec<(String, String)>,
+  pub game: Game,
+  start: GameState,
+  moves: Vec<Move>,
+}
+
+enum Token {
+  Tag(String, String),
+  Symbol(String),
+  Result(String),
+  VariationStart,
+  VariationEnd,
+}
+
+impl PgnGame {
+  pub fn new(game: Game) -> Self {
+    PgnGame {tags: vec![], start: game.state, game, moves: vec![]}
+  }
+
+  pub fn makemove(&mut self, m: &Move) -> Result<(), &'static str> {
+    self.game.makemove(m)?;
+    self.moves.push(*m);
+    Ok(())
+  }
+
+  pub fn get_moves(&self) -> &[Move] {
+    &self.moves
+  }
+
+  pub fn get_tag(&self, name: &str) -> Option<&str> {
+    self.tags.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+  }
+
+  pub fn set_tag(&mut self, name: &str, value: &str) {
+    match self.tags.iter_mut().find(|(n, _)| n == name) {
+      Some(tag) => tag.1 = value.to_string(),
+      None => self.tags.push((name.to_string(), value.to_string())),
+    }
+  }
+
+  // comments, NAGs and variations are accepted but stripped, only the main line is kept and to_pgn
+  // writes back neither variations nor comments. a movetext without a result token takes the
+  // result from the Result tag
+  pub fn from_pgn(pgn: &str) -> Result<Self, &'static str> {
+    let tokens = tokenize(pgn)?;
+
+    let mut tags = vec![];
+    for token in tokens.iter() {
+      if let Token::Tag(name, value) = token {
+        tags.push((name.clone(), value.clone()));
+      }
+    }
+
+    let fen = match tags.iter().find(|(n, _)| n == "FEN") {
+      Some((_, fen)) => fen.as_str(),
+      None => START_FEN,
+    };
+
+    let mut ret = PgnGame::new(Game::from_fen(fen)?);
+    ret.tags = tags;
+
+    let mut depth = 0;
+    let mut result = None;
+
+    for token in tokens.iter() {
+      match token {
+        Token::VariationStart => depth += 1,
+        Token::VariationEnd => {
+          if depth == 0 {
+            return Err("Unbalanced parenthesis in movetext");
+          }
+          depth -= 1;
+        },
+        Token::Symbol(san) if depth == 0 => {
+          let m = Move::from_san(san, &ret.game.state)?;
+          ret.makemove(&m)?;
+        },
+        Token::Result(r) if depth == 0 => result = Some(r.clone()),
+        _ => (),
+      }
+    }
+
+    if depth != 0 {
+      return Err("Unterminated variation in movetext");
+    }
+
+    let result = result.or_else(|| ret.get_tag("Result").map(|r| r.to_string()));
+
+    // results that are not visible on the board
+    if ret.game.get_result() == GameResult::NotDone {
+      let termination = ret.get_tag("Termination").unwrap_or("").to_lowercase();
+      let loser = match result.as_deref() {
+        Some("1-0") => Some(Player::Black),
+        Some("0-1") => Some(Player::White),
+        Some("1/2-1/2") => {
+          ret.game.agree_remis();
+          None
+        },
+        _ => None,
+      };
+
+      if let Some(player) = loser {
+        if termination.contains("time") {
+          ret.game.lose_on_time(player);
+        } else {
+          ret.game.resign(player);
+        }
+      }
+    }
+
+    Ok(ret)
+  }
+
+  pub fn to_pgn(&mut self) -> Result<String, &'static str> {
+    let result = result_token(&self.game.get_result());
+
+    // tags
+    let mut ret = String::new();
+    for (name, default) in SEVEN_TAG_ROSTER {
+      let value = match name {
+        "Result" => result,
+        _ => self.get_tag(name).unwrap_or(default),
+      };
+      ret.push_str(&format_tag(name, value));
+    }
+
+    let start_fen = self.start.to_fen();
+    for (name, value) in self.tags.iter() {
+      let is_roster = SEVEN_TAG_ROSTER.iter().any(|(n, _)| n == name);
+      if !is_roster && name != "SetUp" && name != "FEN" {
+        ret.push_str(&format_tag(name, value));
+      }
+    }
+    if start_fen != START_FEN {
+      ret.push_str(&format_tag("SetUp", "1"));
+      ret.push_str(&format_tag("FEN", &start_fen));
+    }
+    ret.push('\n');
+
+    // movetext
+    let mut replay = Game::from_fen(&start_fen)?;
+    let mut words = vec![];
+
+    for m in self.moves.iter() {
+      // move numbers stay on the same line as their move
+      let number = replay.state.get_fullmove_clock();
+      let san = Move::to_san(m, &mut replay)?;
+      match replay.get_player() {
+        Player::White => words.push(format!("{}. {}", number, san)),
+        Player::Black if words.is_empty() => words.push(format!("{}... {}", number, san)),
+        Player::Black => words.push(san),
+      }
+
+      replay.makemove(m)?;
+    }
+    if replay.state.get_hash() != self.game.state.get_hash() {
+      return Err("Game contains moves that were not made through PgnGame");
+    }
+    words.push(result.to_string());
+
+    let mut line = String::new();
+    for word in words {
+      if !line.is_empty() && line.len() + word.len() + 1 > MAX_LINE_LEN {
+        ret.push_str(&line);
+        ret.push('\n');
+        line.clear();
+      }
+      if !line.is_empty() {
+        line.push(' ');
+      }
+      line.push_str(&word);
+    }
+    ret.push_str(&line);
+    ret.push('\n');
+
+    Ok(ret)
+  }
+}
+
+// reads one game after another from a stream of concatenated PGN games
+pub struct PgnReader<R: BufRead> {
+  reader: R,
+  pending: Option<String>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+  pub fn new(reader: R) -> Self {
+    PgnReader {reader, pending: None}
+  }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+  type Item = Result<PgnGame, &'static str>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let mut text = String::new();
+    let mut in_movetext = false;
+    let mut in_comment = false;
+    let mut depth = 0;
+
+    loop {
+      // the rest of a line after a result token is read again for the next game
+      let line = match self.pending.take() {
+        Some(x) => x,
+        None => {
+          let mut line = String::new();
+          match self.reader.read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => (),
+            Err(_) => return Some(Err("Could not read PGN input")),
+          }
+          line
+        },
+      };
+
+      let trimmed = line.trim();
+
+      if !in_comment && trimmed.starts_with('[') {
+        // a tag after movetext starts the next game
+        if in_movetext {
+          self.pending = Some(line);
+          break;
+        }
+        text.push_str(&line);
+        continue;
+      }
+
+      if !in_comment && trimmed.starts_with('%') {
+        text.push_str(&line);
+        continue;
+      }
+      if !trimmed.is_empty() {
+        in_movetext = true;
+      }
+
+      // a result token ends the game, even when the next game has no tag section
+      let mut word_start = None;
+      let mut result_end = None;
+      for (i, c) in line.char_indices() {
+        let ends_word = !in_comment && depth == 0 && (c.is_whitespace() || "{;(".contains(c));
+        if ends_word && word_start.take().is_some_and(|start| is_result(&line[start..i])) {
+          result_end = Some(i);
+          break;
+        }
+        match (in_comment, c) {
+          (false, '{') => in_comment = true,
+          (false, ';') => break,
+          (false, '(') => depth += 1,
+          (false, ')') => depth -= 1,
+          (true, '}') => in_comment = false,
+          (false, c) if !c.is_whitespace() && word_start.is_none() => word_start = Some(i),
+          _ => (),
+        }
+      }
+      let at_end = word_start.filter(|_| result_end.is_none() && !in_comment && depth == 0);
+      if at_end.is_some_and(|start| is_result(line[start..].trim_end())) {
+        result_end = Some(line.len());
+      }
+
+      if let Some(end) = result_end {
+        text.push_str(&line[..end]);
+        if !line[end..].trim().is_empty() {
+          self.pending = Some(line[end..].to_string());
+        }
+        break;
+      }
+      text.push_str(&line);
+    }
+
+    if text.trim().is_empty() {
+      return None;
+    }
+
+    Some(PgnGame::from_pgn(&text))
+  }
+}
+
+fn format_tag(name: &str, value: &str) -> String {
+  let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+  format!("[{} \"{}\"]\n", name, escaped)
+}
+
+fn result_token(result: &GameResult) -> &'static str {
+  match result {
+    GameResult::Win(Player::White, _) => "1-0",
+    GameResult::Win(Player::Black, _) => "0-1",
+    GameResult::Remis(_) => "1/2-1/2",
+    GameResult::NotDone => "*",
+  }
+}
+
+fn is_result(word: &str) -> bool {
+  matches!(word, "1-0" | "0-1" | "1/2-1/2" | "*")
+}
+
+fn tokenize(pgn: &str) -> Result<Vec<Token>, &'static str> {
+  let mut tokens = vec![];
+  let mut chars = pgn.chars().peekable();
+  let mut line_start = true;
+
+  while let Some(c) = chars.next() {
+    match c {
+      '\n' => {
+        line_start = true;
+        continue;
+      },
+      // escape mechanism, the whole line is ignored
+      '%' if line_start => {
+        for c in chars.by_ref() {
+          if c == '\n' {
+            break;
+          }
+        }
+      },
+      c if c.is_whitespace() => continue,
+      '[' => {
+        let mut tag = String::new();
+        let mut in_string = false;
+        let mut escaped = false;
+        let mut closed = false;
+
+        for c in chars.by_ref() {
+          if in_string {
+            if escaped {
+              escaped = false;
+            } else if c == '\\' {
+              escaped = true;
+            } else if c == '"' {
+              in_string = false;
+            }
+          } else if c == '"' {
+            in_string = true;
+          } else if c == ']' {
+            closed = true;
+            break;
+          }
+          tag.push(c);
+        }
+
+        if !closed {
+          return Err("Unterminated tag pair");
+        }
+        let (name, value) = parse_tag(&tag)?;
+        tokens.push(Token::Tag(name, value));
+      },
+      '{' => {
+        let mut closed = false;
+        for c in chars.by_ref() {
+          if c == '}' {
+            closed = true;
+            break;
+          }
+        }
+        if !closed {
+          return Err("Unterminated comment");
+        }
+      },
+      ';' => {
+        for c in chars.by_ref() {
+          if c == '\n' {
+            break;
+          }
+        }
+      },
+      '(' => tokens.push(Token::VariationStart),
+      ')' => tokens.push(Token::VariationEnd),
+      // numeric annotation glyph
+      '$' => {
+        while chars.peek().is_some_and(|c| c.is_ascii_digit()) {
+          chars.next();
+        }
+      },
+      _ => {
+        let mut symbol = String::from(c);
+        while let Some(&c) = chars.peek() {
+          if c.is_whitespace() || "{}()[];$".contains(c) {
+            break;
+          }
+          symbol.push(c);
+          chars.next();
+        }
+
+        match symbol.as_str() {
+          "1-0" | "0-1" | "1/2-1/2" | "*" => tokens.push(Token::Result(symbol)),
+          _ => {
+            // strip move numbers ("12." / "12...") and suffix annotations ("!?")
+            let san = match symbol.rfind('.') {
+              Some(idx) => &symbol[idx + 1..],
+              None => symbol.as_str(),
+            };
+            let san = san.trim_end_matches(['!', '?']);
+
+            if !san.is_empty() {
+              tokens.push(Token::Symbol(san.to_string()));
+            }
+          },
+        }
+      },
+    }
+
+    line_start = false;
+  }
+
+  Ok(tokens)
+}
+
+fn parse_tag(tag: &str) -> Result<(String, String), &'static str> {
+  let tag = tag.trim();
+  let (name, rest) = match tag.split_once(char::is_whitespace) {
+    Some(x) => x,
+    None => return Err("Tag pair has no value"),
+  };
+
+  let rest = rest.trim();
+  if rest.len() < 2 || !rest.starts_with('"') || !rest.ends_with('"') {
+    return Err("Tag value is not a string");
+  }
+
+  let mut value = String::new();
+  let mut escaped = false;
+  for c in rest[1..rest.len() - 1].chars() {
+    if !escaped && c == '\\' {
+      escaped = true;
+      continue;
+    }
+    escaped = false;
+    value.push(c);
+  }
+
+  Ok((name.to_string(), value))
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+pub const MAX_DEPTH: u32 = 64;
+// the quiescence search may go beyond the nominal depth
+pub const MAX_PLY: u32 = 2 * MAX_DEPTH;
+
+// centipawns, or a mate given as the distance in plies from MATE
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Score(pub i32);
+
+impl Score {
+  pub const ZERO: Score = Score(0);
+  pub const INFINITY: Score = Score(32_000);
+  // the side to move mates right now
+  pub const MATE: Score = Score(31_000);
+  // everything beyond is a mate, no search goes deeper than MAX_PLY
+  pub const MATE_BOUND: Score = Score(31_000 - MAX_PLY as i32);
+
+  pub const fn cp(cp: i32) -> Self {
+    Score(cp)
+  }
+
+  // the side to move mates in `ply` plies
+  pub const fn mate_in(ply: u32) -> Self {
+    Score(Score::MATE.0 - ply as i32)
+  }
+
+  // the side to move is mated in `ply` plies
+  pub const fn mated_in(ply: u32) -> Self {
+    Score(-Score::MATE.0 + ply as i32)
+  }
+
+  pub fn is_mate(self) -> bool {
+    self.0.abs() >= Score::MATE_BOUND.0
+  }
+
+  // full moves to the mate, negative if the side to move is mated
+  pub fn mate_moves(self) -> Option<i32> {
+    if !self.is_mate() {
+      return None;
+    }
+
+    let moves = (Score::MATE.0 - self.0.abs() + 1) / 2;
+    if self.0 > 0 { Some(moves) } else { Some(-moves) }
+  }
+
+  // mates are stored relative to the node instead of the root, so they stay valid at every ply
+  pub fn to_tt(self, ply: u32) -> Self {
+    match self {
+      s if s >= Score::MATE_BOUND => Score(s.0 + ply as i32),
+      s if s <= -Score::MATE_BOUND => Score(s.0 - ply as i32),
+      s => s,
+    }
+  }
+
+  pub fn from_tt(self, ply: u32) -> Self {
+    match self {
+      s if s >= Score::MATE_BOUND => Score(s.0 - ply as i32),
+      s if s <= -Score::MATE_BOUND => Score(s.0 + ply as i32),
+      s => s,
+    }
+  }
+}
+
+// `cp x` or `mate n` as used by UCI info lines
+impl fmt::Display for Score {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.mate_moves() {
+      Some(n) => write!(f, "mate {}", n),
+      None => write!(f, "cp {}", self.0),
+    }
+  }
+}
+
+impl Neg for Score {
+  type Output = Score;
+
+  fn neg(self) -> Score {
+    Score(-self.0)
+  }
+}
+
+impl Add for Score {
+  type Output = Score;
+
+  fn add(self, other: Score) -> Score {
+    Score(self.0 + other.0)
+  }
+}
+
+impl Sub for Score {
+  type Output = Score;
+
+  fn sub(self, other: Score) -> Score {
+    Score(self.0 - other.0)
+  }
+}
+
+impl Mul<i32> for Score {
+  type Output = Score;
+
+  fn mul(self, factor: i32) -> Score {
+    Score(self.0 * factor)
+  }
+}
@@ -1,94 +1,606 @@
 use crate::game::{Game, GameState};
-use crate::movegen::{Move};
-use crate::board::{Player, Pieces};
-
-const PAWN_V: f64 = 100.0;
-const KNIGHT_V: f64 = 320.0;
-const BISHOP_V: f64 = 330.0;
-const ROOK_V: f64 = 500.0;
-const QUEEN_V: f64 = 900.0;
-const KING_V: f64 = 1000000.0;
-
-#[inline]
-pub fn max(a: f64, b: f64) -> f64 {
-  if a > b {
-    a
-  } else {
-    b
+use crate::eval::{eval_cached};
+use crate::movegen::{Move, MoveGen};
+use crate::board::{Board, Player, Pieces};
+use crate::score::{Score, MAX_DEPTH, MAX_PLY};
+use crate::tt::{Bound, TranspositionTable};
+use crate::pawns::{PawnTable};
+use crate::params::{EvalParams};
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+// only used to order captures by MVV-LVA, the eval takes its piece values from the params
+const ORDER_PAWN: i32 = 100;
+const ORDER_KNIGHT: i32 = 320;
+const ORDER_BISHOP: i32 = 330;
+const ORDER_ROOK: i32 = 500;
+const ORDER_QUEEN: i32 = 900;
+
+// safety margin kept on the clock for communication overhead
+const MOVE_OVERHEAD: u64 = 50;
+// moves assumed to be left when the GUI does not send movestogo
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+// plies searched by a plain `go` without any limits
+const DEFAULT_DEPTH: u32 = 5;
+
+// all times are in milliseconds, as sent by the UCI `go` command
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchLimits {
+  pub wtime: Option<u64>,
+  pub btime: Option<u64>,
+  pub winc: Option<u64>,
+  pub binc: Option<u64>,
+  pub movestogo: Option<u64>,
+  pub movetime: Option<u64>,
+  pub depth: Option<u32>,
+  pub nodes: Option<u64>,
+  pub mate: Option<u32>,
+  pub infinite: bool,
+  pub ponder: bool,
+  pub searchmoves: Vec<Move>,
+}
+
+impl SearchLimits {
+  pub fn depth(depth: u32) -> Self {
+    SearchLimits {depth: Some(depth), ..Default::default()}
+  }
+
+  // time the search may use for this move, None if it is not bound by time
+  pub fn time_budget(&self, player: Player) -> Option<Duration> {
+    if self.infinite {
+      return None;
+    }
+
+    if let Some(t) = self.movetime {
+      return Some(Duration::from_millis(t));
+    }
+
+    let (time, inc) = match player {
+      Player::White => (self.wtime?, self.winc.unwrap_or(0)),
+      Player::Black => (self.btime?, self.binc.unwrap_or(0)),
+    };
+
+    let moves_to_go = self.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+    let usable = time.saturating_sub(MOVE_OVERHEAD).max(time / 2);
+    let budget = (time / moves_to_go + inc * 3 / 4).min(usable).max(1);
+
+    Some(Duration::from_millis(budget))
+  }
+
+  // plies to search, a mate in n needs at most 2n - 1 plies
+  pub fn max_depth(&self) -> u32 {
+    let unbounded = !self.infinite && self.nodes.is_none() && self.movetime.is_none() &&
+                    self.wtime.is_none() && self.btime.is_none();
+
+    let mut depth = match self.depth {
+      Some(d) => d,
+      None if unbounded && self.mate.is_none() => DEFAULT_DEPTH,
+      None => MAX_DEPTH,
+    };
+    if let Some(n) = self.mate {
+      depth = depth.min(2 * n.max(1) - 1);
+    }
+    depth.clamp(1, MAX_DEPTH)
   }
 }
 
-pub fn eval(s: &mut GameState) -> f64 {
-  let v: f64 = PAWN_V * ((s.count_pieces(Player::White, Pieces::Pawn) as f64) - (s.count_pieces(Player::Black, Pieces::Pawn) as f64)) +
-          KNIGHT_V * ((s.count_pieces(Player::White, Pieces::Knight) as f64) - (s.count_pieces(Player::Black, Pieces::Knight) as f64)) +
-          BISHOP_V * ((s.count_pieces(Player::White, Pieces::Bishop) as f64) - (s.count_pieces(Player::Black, Pieces::Bishop) as f64))+
-          ROOK_V * ((s.count_pieces(Player::White, Pieces::Rook) as f64) - (s.count_pieces(Player::Black, Pieces::Rook) as f64)) +
-          QUEEN_V * ((s.count_pieces(Player::White, Pieces::Queen) as f64) - (s.count_pieces(Player::Black, Pieces::Queen) as f64)) +
-          KING_V * ((s.count_pieces(Player::White, Pieces::King) as f64) - (s.count_pieces(Player::Black, Pieces::King) as f64));
-  v
+// statistics of a finished iteration, the value is from white's point of view
+#[derive(Debug, Clone)]
+pub struct SearchInfo {
+  pub depth: u32,
+  pub seldepth: u32,
+  pub value: Score,
+  pub nodes: u64,
+  pub time: Duration,
+  pub pv: Vec<Move>,
+  pub first_move_cutoffs: f64,
+}
 
+pub struct Searcher<'a> {
+  limits: SearchLimits,
+  tt: &'a mut TranspositionTable,
+  params: Arc<EvalParams>,
+  pawn_table: &'a mut PawnTable,
+  // without it the leaves get the static eval, which a plain minimax can reproduce
+  use_quiescence: bool,
+  // without it the moves are searched in generator order, only there to measure the ordering
+  use_ordering: bool,
+  // set by the front end to abort the search
+  stop: Arc<AtomicBool>,
+  // cleared by the front end on ponderhit, the clock only runs once it is cleared
+  ponder: Arc<AtomicBool>,
+  pondering: bool,
+  // start of the search for info lines, `clock_start` is where the time budget starts to run
+  start: Instant,
+  clock_start: Instant,
+  budget: Option<Duration>,
+  nodes: u64,
+  seldepth: u32,
+  aborted: bool,
+  // quiet moves that caused a beta cutoff, two per ply
+  killers: [[Option<Move>; 2]; MAX_PLY as usize],
+  // cutoffs of quiet moves indexed by player and the relative from and to square
+  history: [[[u32; 64]; 64]; 2],
+  cutoffs: u64,
+  first_move_cutoffs: u64,
+  // triangular pv table, pv[ply] is the best line found from the node at that ply
+  pv: Vec<Vec<Move>>,
 }
 
-pub fn alphabeta(game: &mut Game, depth: u32, mut alpha: f64, beta:f64, color: i32) -> f64 {
-  if depth == 0 || game.is_checkmate(game.get_player()) || game.is_remis() {
-    return eval(&mut game.state) * (color as f64);
+impl<'a> Searcher<'a> {
+  pub fn new(limits: SearchLimits, tt: &'a mut TranspositionTable, pawn_table: &'a mut PawnTable, stop: Arc<AtomicBool>, ponder: Arc<AtomicBool>) -> Self {
+    Searcher {limits, tt, params: Arc::new(EvalParams::default()), pawn_table, use_quiescence: true, use_ordering: true, stop, ponder, pondering: false, start: Instant::now(), clock_start: Instant::now(), budget: None, nodes: 0, seldepth: 0,
+              aborted: false, killers: [[None; 2]; MAX_PLY as usize], history: [[[0; 64]; 64]; 2], cutoffs: 0,
+              first_move_cutoffs: 0, pv: vec![vec![]; MAX_PLY as usize + 1]}
+  }
+
+  // the pawn table belongs to the caller, it has to be cleared when the weights change
+  pub fn set_params(&mut self, params: Arc<EvalParams>) {
+    self.params = params;
+  }
+
+  pub fn set_quiescence(&mut self, on: bool) {
+    self.use_quiescence = on;
+  }
+
+  pub fn set_ordering(&mut self, on: bool) {
+    self.use_ordering = on;
+  }
+
+  pub fn get_nodes(&self) -> u64 {
+    self.nodes
+  }
+
+  pub fn elapsed(&self) -> Duration {
+    self.start.elapsed()
+  }
+
+  // share of beta cutoffs that were caused by the first move searched, a measure of the move ordering
+  pub fn first_move_cutoff_rate(&self) -> f64 {
+    if self.cutoffs == 0 {
+      return 0.0;
+    }
+    self.first_move_cutoffs as f64 / self.cutoffs as f64
   }
- 
-  let moves = game.legal_moves();
- 
-  let mut best_v = -std::f64::INFINITY;
 
-  for m in moves.iter() {
-    game.makemove(m).unwrap();
-    let v = -alphabeta(game, depth-1, -beta, -alpha, -color);
-    game.undo_move();
+  fn should_stop(&mut self) -> bool {
+    if self.aborted {
+      return true;
+    }
 
-    if v > best_v {
-      best_v = v;
+    if self.limits.nodes.is_some_and(|n| self.nodes >= n) {
+      self.aborted = true;
     }
 
-    alpha = max(alpha, v);
-    if alpha >= beta {
-      break;
+    // checking the clock on every node is too expensive
+    if self.nodes & 1023 == 0 {
+      if self.stop.load(Ordering::Relaxed) {
+        self.aborted = true;
+      }
+
+      if self.out_of_time() {
+        self.aborted = true;
+      }
     }
+
+    self.aborted
   }
 
-  best_v
-}
+  // after a ponderhit the move is ours and the time budget starts
+  fn check_ponderhit(&mut self) {
+    if self.pondering && !self.ponder.load(Ordering::Relaxed) {
+      self.pondering = false;
+      self.clock_start = Instant::now();
+    }
+  }
 
-pub fn root_search(game: &mut Game, depth: u32) -> (Option<Move>, f64) {
-  let moves = game.legal_moves();
-  
-  let mut best_move = None;
-  let mut best_v = match game.state.get_player() {
-    Player::White => -std::f64::INFINITY,
-    Player::Black => std::f64::INFINITY,
-  };
+  fn out_of_time(&mut self) -> bool {
+    self.check_ponderhit();
+    !self.pondering && self.budget.is_some_and(|b| self.clock_start.elapsed() >= b)
+  }
 
-  if game.state.get_player() == Player::White {
-    for m in moves.iter() {
+  // searches deeper and deeper until a limit is hit, the result of the last finished depth is returned.
+  // `report` is called after every finished iteration
+  pub fn go<F: FnMut(&SearchInfo)>(&mut self, game: &mut Game, mut report: F) -> (Vec<Move>, Score) {
+    self.start = Instant::now();
+    self.clock_start = self.start;
+    self.budget = self.limits.time_budget(game.get_player());
+    self.nodes = 0;
+    self.aborted = self.stop.load(Ordering::Relaxed);
+    self.pondering = self.limits.ponder && self.ponder.load(Ordering::Relaxed);
+    self.tt.new_search();
+    self.killers = [[None; 2]; MAX_PLY as usize];
+    self.history = [[[0; 64]; 64]; 2];
+    self.cutoffs = 0;
+    self.first_move_cutoffs = 0;
+
+    // mate or stalemate on the board, there is nothing to search or report
+    if self.root_moves(game).is_empty() {
+      return self.search_root(game, 0);
+    }
+
+    let mut best = (vec![], Score::ZERO);
+
+    for depth in 1..=self.limits.max_depth() {
+      self.seldepth = 0;
+      let (pv, v) = self.search_root(game, depth - 1);
+
+      if self.aborted {
+        // an unfinished iteration is only used if nothing else is there
+        if best.0.is_empty() {
+          best = (pv, v);
+        }
+        break;
+      }
+
+      report(&SearchInfo {
+        depth,
+        seldepth: self.seldepth,
+        value: v,
+        nodes: self.nodes,
+        time: self.start.elapsed(),
+        pv: pv.clone(),
+        first_move_cutoffs: self.first_move_cutoff_rate(),
+      });
+      best = (pv, v);
+
+      // the search is full width, deeper iterations cannot find a shorter mate
+      if v.is_mate() {
+        break;
+      }
+
+      // another iteration takes longer than all previous ones together
+      self.check_ponderhit();
+      if !self.pondering && self.budget.is_some_and(|b| self.clock_start.elapsed() * 2 >= b) {
+        break;
+      }
+    }
+
+    if best.0.is_empty() {
+      best.0 = self.root_moves(game).into_iter().take(1).collect();
+    }
+
+    best
+  }
+
+  // the line of the node at `ply` becomes `m` followed by the line of its child
+  fn update_pv(&mut self, ply: u32, m: Move) {
+    let (head, tail) = self.pv.split_at_mut(ply as usize + 1);
+    let line = &mut head[ply as usize];
+
+    line.clear();
+    line.push(m);
+    line.extend_from_slice(&tail[0]);
+  }
+
+  fn root_moves(&self, game: &mut Game) -> Vec<Move> {
+    let moves = game.legal_moves();
+
+    if self.limits.searchmoves.is_empty() {
+      return moves;
+    }
+
+    moves.into_iter().filter(|m| self.limits.searchmoves.contains(m)).collect()
+  }
+
+  pub fn alphabeta(&mut self, game: &mut Game, depth: u32, ply: u32, mut alpha: Score, beta: Score, color: i32) -> Score {
+    self.nodes += 1;
+    self.seldepth = self.seldepth.max(ply);
+    self.pv[ply as usize].clear();
+
+    if self.should_stop() {
+      return Score::ZERO;
+    }
+
+    if game.is_threefold_repetition() || game.is_insufficient_material() {
+      return Score::ZERO;
+    }
+
+    if depth == 0 {
+      if !self.use_quiescence {
+        return eval_cached(&game.state, &self.params, self.pawn_table) * color;
+      }
+      return self.quiescence(game, ply, alpha, beta, color);
+    }
+
+    // quicker mates score higher
+    let mut moves = game.legal_moves();
+    if moves.is_empty() {
+      return if game.state.is_check(game.get_player()) { Score::mated_in(ply) } else { Score::ZERO };
+    }
+
+    if game.is_fifty_moves() {
+      return Score::ZERO;
+    }
+
+    let key = game.state.get_hash();
+    let mut tt_move = None;
+
+    if let Some(e) = self.tt.probe(key, ply) {
+      tt_move = e.best_move;
+
+      if e.depth >= depth {
+        match e.bound {
+          Bound::Exact => return e.value,
+          Bound::Lower if e.value >= beta => return e.value,
+          Bound::Upper if e.value <= alpha => return e.value,
+          _ => (),
+        }
+      }
+    }
+
+    if self.use_ordering {
+      self.order_moves(&game.state, &mut moves, tt_move, ply);
+    }
+
+    let alpha_orig = alpha;
+    let mut best_v = -Score::INFINITY;
+    let mut best_move = None;
+
+    for (i, m) in moves.iter().enumerate() {
       game.makemove(m).unwrap();
-      let v = -alphabeta(game, depth, -std::f64::INFINITY, std::f64::INFINITY, -1);
+      let v = -self.alphabeta(game, depth-1, ply+1, -beta, -alpha, -color);
       game.undo_move();
 
+      if self.aborted {
+        return Score::ZERO;
+      }
 
       if v > best_v {
         best_v = v;
         best_move = Some(*m);
       }
+
+      if v > alpha {
+        self.update_pv(ply, *m);
+      }
+
+      alpha = alpha.max(v);
+      if alpha >= beta {
+        self.cutoffs += 1;
+        if i == 0 {
+          self.first_move_cutoffs += 1;
+        }
+        if !is_tactical(&game.state.get_relative_board(), m) {
+          self.store_quiet_cutoff(game.get_player(), m, depth, ply);
+        }
+        break;
+      }
     }
-  } else {
+
+    let bound = if best_v <= alpha_orig {
+      Bound::Upper
+    } else if best_v >= beta {
+      Bound::Lower
+    } else {
+      Bound::Exact
+    };
+    self.tt.store(key, depth, bound, best_v, best_move, ply);
+
+    best_v
+  }
+
+  // hash move, captures and promotions by MVV-LVA, killers and the remaining quiet moves by history
+  fn order_moves(&self, state: &GameState, moves: &mut [Move], tt_move: Option<Move>, ply: u32) {
+    let board = state.get_relative_board();
+    let player = state.get_player() as usize;
+    let killers = self.killers[ply as usize];
+
+    moves.sort_by_cached_key(|m| {
+      let score = if Some(*m) == tt_move {
+        i64::MAX
+      } else if is_tactical(&board, m) {
+        CAPTURE_SCORE + mvv_lva(&board, m) as i64
+      } else if Some(*m) == killers[0] {
+        KILLER_SCORE
+      } else if Some(*m) == killers[1] {
+        KILLER_SCORE - 1
+      } else {
+        self.history[player][m.from as usize][m.to as usize] as i64
+      };
+      -score
+    });
+  }
+
+  fn store_quiet_cutoff(&mut self, player: Player, m: &Move, depth: u32, ply: u32) {
+    let killers = &mut self.killers[ply as usize];
+    if killers[0] != Some(*m) {
+      killers[1] = killers[0];
+      killers[0] = Some(*m);
+    }
+
+    // deeper cutoffs say more about a move, old entries fade once the values grow large
+    let history = &mut self.history[player as usize];
+    history[m.from as usize][m.to as usize] += depth * depth;
+    if history[m.from as usize][m.to as usize] >= MAX_HISTORY {
+      for v in history.iter_mut().flat_map(|x| x.iter_mut()) {
+        *v /= 2;
+      }
+    }
+  }
+
+  // only captures and promotions are searched until the position is quiet, so the eval is not
+  // taken in the middle of an exchange. in check every evasion is searched instead and the static
+  // eval does not count, MAX_PLY ends a line of checks that never gets quiet
+  pub fn quiescence(&mut self, game: &mut Game, ply: u32, mut alpha: Score, beta: Score, color: i32) -> Score {
+    self.nodes += 1;
+    self.seldepth = self.seldepth.max(ply);
+    self.pv[ply as usize].clear();
+
+    if self.should_stop() {
+      return Score::ZERO;
+    }
+
+    if ply >= MAX_PLY {
+      return eval_cached(&game.state, &self.params, self.pawn_table) * color;
+    }
+
+    let (mut best_v, mut moves) = if game.state.is_check(game.get_player()) {
+      let moves = game.legal_moves();
+      if moves.is_empty() {
+        return Score::mated_in(ply);
+      }
+      (-Score::INFINITY, moves)
+    } else {
+      // the side to move can usually do at least as well as the static eval by not capturing
+      let stand_pat = eval_cached(&game.state, &self.params, self.pawn_table) * color;
+      if stand_pat >= beta {
+        return stand_pat;
+      }
+      alpha = alpha.max(stand_pat);
+      (stand_pat, MoveGen::captures(&game.state))
+    };
+
+    // most valuable victim first, least valuable attacker breaks ties
+    let board = game.state.get_relative_board();
+    moves.sort_by_cached_key(|m| -mvv_lva(&board, m));
+
     for m in moves.iter() {
-      game.makemove(m).unwrap();
-      let v = -alphabeta(game, depth, -std::f64::INFINITY, std::f64::INFINITY, 1);
+      if game.makemove(m).is_err() {
+        continue;
+      }
+      let v = -self.quiescence(game, ply+1, -beta, -alpha, -color);
       game.undo_move();
 
-      if v < best_v {
+      if self.aborted {
+        return Score::ZERO;
+      }
+
+      if v > best_v {
         best_v = v;
-        best_move = Some(*m);
+      }
+
+      if v > alpha {
+        self.update_pv(ply, *m);
+      }
+
+      alpha = alpha.max(v);
+      if alpha >= beta {
+        break;
       }
     }
+
+    best_v
   }
 
-  (best_move, best_v)
+  // values are from white's point of view, white maximizes and black minimizes
+  // returns the principal variation and its value
+  pub fn search_root(&mut self, game: &mut Game, depth: u32) -> (Vec<Move>, Score) {
+    let key = game.state.get_hash();
+    let mut moves = self.root_moves(game);
+    order_tt_move(&mut moves, self.tt.probe(key, 0).and_then(|e| e.best_move));
+    self.pv[0].clear();
+
+    if moves.is_empty() {
+      let player = game.get_player();
+      let v = if game.state.is_check(player) { Score::mated_in(0) } else { Score::ZERO };
+
+      return match player {
+        Player::White => (vec![], v),
+        Player::Black => (vec![], -v),
+      };
+    }
+
+    let mut best_move = None;
+    let mut best_v = match game.state.get_player() {
+      Player::White => -Score::INFINITY,
+      Player::Black => Score::INFINITY,
+    };
+
+    // later moves only have to show that they are not better than the best one so far
+    if game.state.get_player() == Player::White {
+      for m in moves.iter() {
+        game.makemove(m).unwrap();
+        let v = -self.alphabeta(game, depth, 1, -Score::INFINITY, -best_v, -1);
+        game.undo_move();
+
+        if self.aborted {
+          break;
+        }
+
+        if v > best_v {
+          best_v = v;
+          best_move = Some(*m);
+          self.update_pv(0, *m);
+        }
+      }
+    } else {
+      for m in moves.iter() {
+        game.makemove(m).unwrap();
+        let v = self.alphabeta(game, depth, 1, -Score::INFINITY, best_v, 1);
+        game.undo_move();
+
+        if self.aborted {
+          break;
+        }
+
+        if v < best_v {
+          best_v = v;
+          best_move = Some(*m);
+          self.update_pv(0, *m);
+        }
+      }
+    }
+
+    // the next iteration starts with the best move of this one
+    if !self.aborted && best_move.is_some() {
+      let v = match game.state.get_player() {
+        Player::White => best_v,
+        Player::Black => -best_v,
+      };
+      self.tt.store(key, depth + 1, Bound::Exact, v, best_move, 0);
+    }
+
+    (self.pv[0].clone(), best_v)
+  }
+}
+
+// order of the move classes, history values stay below the killers
+const CAPTURE_SCORE: i64 = 2_000_000;
+const KILLER_SCORE: i64 = 1_000_000;
+const MAX_HISTORY: u32 = 500_000;
+
+fn is_tactical(board: &Board, m: &Move) -> bool {
+  m.ep || m.promotion.is_some() || board.get_piece(m.to as i32).is_some()
+}
+
+fn piece_value(piece: Pieces) -> i32 {
+  match piece {
+    Pieces::Pawn => ORDER_PAWN,
+    Pieces::Knight => ORDER_KNIGHT,
+    Pieces::Bishop => ORDER_BISHOP,
+    Pieces::Rook => ORDER_ROOK,
+    Pieces::Queen => ORDER_QUEEN,
+    // the king is never captured, its value only makes it the last attacker
+    Pieces::King => 2 * ORDER_QUEEN,
+  }
+}
+
+// squares of the board are relative to the player to move, like the squares of the move
+fn mvv_lva(board: &Board, m: &Move) -> i32 {
+  let victim = match board.get_piece(m.to as i32) {
+    Some((_, piece)) => piece_value(piece),
+    None if m.ep => ORDER_PAWN,
+    None => 0,
+  };
+  let promotion = m.promotion.map_or(0, piece_value);
+
+  (victim + promotion) * 16 - piece_value(m.piece) / 100
+}
+
+// the hash move is searched first, it is only used if it is legal in this position
+fn order_tt_move(moves: &mut [Move], tt_move: Option<Move>) {
+  if let Some(idx) = tt_move.and_then(|t| moves.iter().position(|m| *m == t)) {
+    moves[..=idx].rotate_right(1);
+  }
+}
+
+pub fn root_search(game: &mut Game, depth: u32) -> (Vec<Move>, Score) {
+  let stop = Arc::new(AtomicBool::new(false));
+  let ponder = Arc::new(AtomicBool::new(false));
+  let mut tt = TranspositionTable::new(1);
+  let mut pawn_table = PawnTable::new();
+  Searcher::new(SearchLimits::default(), &mut tt, &mut pawn_table, stop, ponder).search_root(game, depth)
 }
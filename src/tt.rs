@@ -0,0 +1,98 @@
+use crate::movegen::{Move};
+use crate::score::{Score};
+
+use std::mem;
+
+pub const DEFAULT_HASH_MB: usize = 16;
+pub const MAX_HASH_MB: usize = 1024;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Bound {
+  Exact,
+  // the value is at least this high (fail high)
+  Lower,
+  // the value is at most this high (fail low)
+  Upper,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct TTEntry {
+  pub key: u64,
+  pub depth: u32,
+  pub bound: Bound,
+  pub value: Score,
+  pub best_move: Option<Move>,
+  generation: u8,
+}
+
+pub struct TranspositionTable {
+  entries: Vec<Option<TTEntry>>,
+  generation: u8,
+}
+
+impl Default for TranspositionTable {
+  fn default() -> Self {
+    Self::new(DEFAULT_HASH_MB)
+  }
+}
+
+impl TranspositionTable {
+  pub fn new(mb: usize) -> Self {
+    let mut ret = TranspositionTable {entries: vec![], generation: 0};
+    ret.resize(mb);
+    ret
+  }
+
+  // the number of entries is rounded down to a power of two so the index is a simple mask
+  pub fn resize(&mut self, mb: usize) {
+    let n = (mb.clamp(1, MAX_HASH_MB) << 20) / mem::size_of::<Option<TTEntry>>();
+    let n = 1usize << (usize::BITS - 1 - n.leading_zeros());
+
+    self.entries = vec![None; n];
+    self.generation = 0;
+  }
+
+  pub fn clear(&mut self) {
+    self.entries.fill(None);
+    self.generation = 0;
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.entries.len()
+  }
+
+  // entries of older searches are replaced first
+  pub fn new_search(&mut self) {
+    self.generation = self.generation.wrapping_add(1);
+  }
+
+  #[inline]
+  fn index(&self, key: u64) -> usize {
+    (key as usize) & (self.entries.len() - 1)
+  }
+
+  pub fn probe(&self, key: u64, ply: u32) -> Option<TTEntry> {
+    match self.entries[self.index(key)] {
+      Some(e) if e.key == key => Some(TTEntry {value: e.value.from_tt(ply), ..e}),
+      _ => None,
+    }
+  }
+
+  pub fn store(&mut self, key: u64, depth: u32, bound: Bound, value: Score, best_move: Option<Move>, ply: u32) {
+    let idx = self.index(key);
+    let generation = self.generation;
+
+    // deeper entries of the current search are only replaced by the same position
+    if let Some(old) = self.entries[idx] && old.generation == generation && old.key != key && old.depth > depth {
+      return;
+    }
+
+    // keep the old best move if this search did not find one
+    let best_move = match self.entries[idx] {
+      Some(old) if old.key == key && best_move.is_none() => old.best_move,
+      _ => best_move,
+    };
+
+    self.entries[idx] = Some(TTEntry {key, depth, bound, value: value.to_tt(ply), best_move, generation});
+  }
+}
@@ -0,0 +1,166 @@
+use crate::game::{GameState};
+use crate::eval::{eval_with};
+use crate::params::{EvalParams};
+
+use std::fs;
+use std::thread;
+
+// a quiet position and the result of the game it was taken from
+#[derive(Copy, Clone)]
+pub struct TuneEntry {
+  pub state: GameState,
+  // 1 for a white win, 0.5 for a draw and 0 for a black win
+  pub result: f64,
+}
+
+fn parse_result(token: &str) -> Option<f64> {
+  let token = token.trim_matches(|c| c == '"' || c == ';' || c == '[' || c == ']' || c == '(' || c == ')');
+
+  match token {
+    "1-0" => Some(1.0),
+    "0-1" => Some(0.0),
+    "1/2-1/2" => Some(0.5),
+    // a plain 0 or 1 could as well be a move counter
+    _ if token.contains('.') => token.parse::<f64>().ok().filter(|r| (0.0..=1.0).contains(r)),
+    _ => None,
+  }
+}
+
+// either an EPD line like `<fen> c9 "1-0";` or a FEN followed by the result as 1-0, 1/2-1/2, 0-1,
+// or a number with a decimal point like [1.0], 0.5 or [0.0]. the move counters of the FEN are optional
+pub fn parse_entry(line: &str) -> Result<TuneEntry, &'static str> {
+  let tokens: Vec<&str> = line.split_whitespace().collect();
+  if tokens.len() < 5 {
+    return Err("Expected a FEN and a result");
+  }
+
+  // the counters are only there if something follows them
+  let counters = tokens.len() > 6 && tokens[4..6].iter().all(|t| t.parse::<u32>().is_ok());
+  let (fen, rest) = if counters {
+    (tokens[..6].join(" "), &tokens[6..])
+  } else {
+    (format!("{} 0 1", tokens[..4].join(" ")), &tokens[4..])
+  };
+
+  // other EPD operations can hold numbers as well, so the c9 operation wins
+  let result = match rest.iter().position(|t| *t == "c9") {
+    Some(i) => rest.get(i + 1).and_then(|t| parse_result(t)),
+    None => rest.iter().rev().find_map(|t| parse_result(t)),
+  };
+  let result = result.ok_or("No game result after the FEN")?;
+
+  Ok(TuneEntry {state: GameState::from_fen(&fen)?, result})
+}
+
+// lines that are empty or start with # are skipped
+pub fn load_entries(path: &str) -> Result<Vec<TuneEntry>, &'static str> {
+  let text = fs::read_to_string(path).map_err(|_| "Could not read the positions")?;
+
+  text.lines()
+    .map(str::trim)
+    .filter(|l| !l.is_empty() && !l.starts_with('#'))
+    .map(parse_entry)
+    .collect()
+}
+
+// expected score of white for a value in centipawns
+pub fn sigmoid(cp: f64, k: f64) -> f64 {
+  1.0 / (1.0 + 10f64.powf(-k * cp / 400.0))
+}
+
+// mean squared difference between the results and the predictions, split over `threads` threads
+pub fn error(entries: &[TuneEntry], params: &EvalParams, k: f64, threads: usize) -> f64 {
+  if entries.is_empty() {
+    return 0.0;
+  }
+
+  let chunk = entries.len().div_ceil(threads.max(1));
+  let sum: f64 = thread::scope(|scope| {
+    let workers: Vec<_> = entries.chunks(chunk).map(|part| scope.spawn(move || {
+      part.iter().map(|e| (e.result - sigmoid(eval_with(&e.state, params).0 as f64, k)).powi(2)).sum::<f64>()
+    })).collect();
+
+    workers.into_iter().map(|w| w.join().unwrap()).sum()
+  });
+
+  sum / entries.len() as f64
+}
+
+// the scaling constant of the sigmoid that fits the current weights best, searched with finer and
+// finer steps around the best value so far
+pub fn fit_k(entries: &[TuneEntry], params: &EvalParams, threads: usize) -> f64 {
+  let mut best_k = 1.0;
+  let mut best_e = error(entries, params, best_k, threads);
+  let mut step = 0.5;
+
+  while step >= 0.001 {
+    let center = best_k;
+    for i in -5..=5 {
+      let k = center + i as f64 * step;
+      if k <= 0.0 {
+        continue;
+      }
+
+      let e = error(entries, params, k, threads);
+      if e < best_e {
+        best_e = e;
+        best_k = k;
+      }
+    }
+    step /= 10.0;
+  }
+
+  best_k
+}
+
+pub struct TuneOptions {
+  pub threads: usize,
+  // passes over all parameters, the search also ends once a pass does not improve anything
+  pub iterations: usize,
+  pub step: i32,
+}
+
+impl Default for TuneOptions {
+  fn default() -> Self {
+    TuneOptions {threads: thread::available_parallelism().map_or(1, |n| n.get()), iterations: 100, step: 1}
+  }
+}
+
+// texel's local search: every value is moved up and down by one step and the change is kept if it
+// lowers the error. `report` gets the pass and the error after every pass
+pub fn tune<F: FnMut(usize, f64, &EvalParams)>(entries: &[TuneEntry], start: &EvalParams, k: f64, options: &TuneOptions,
+                                               mut report: F) -> EvalParams {
+  let mut params = start.clone();
+  let mut values = params.values();
+  let mut best_e = error(entries, &params, k, options.threads);
+
+  for pass in 1..=options.iterations {
+    let mut improved = false;
+
+    for i in 0..values.len() {
+      let old = values[i];
+
+      for delta in [options.step, -options.step] {
+        values[i] = old + delta;
+        params.set_values(&values);
+
+        let e = error(entries, &params, k, options.threads);
+        if e < best_e {
+          best_e = e;
+          improved = true;
+          break;
+        }
+        values[i] = old;
+      }
+    }
+
+    params.set_values(&values);
+    report(pass, best_e, &params);
+
+    if !improved {
+      break;
+    }
+  }
+
+  params
+}
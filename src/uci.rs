@@ -0,0 +1,430 @@
+use std::io::{self, BufRead, Write};
+
+use crate::board::{Player};
+use crate::game::{Game, GameState};
+use crate::movegen::{Move};
+use crate::pgn::{START_FEN};
+use crate::search::{SearchInfo, SearchLimits, Searcher};
+use crate::tt::{self, TranspositionTable};
+use crate::perft;
+use crate::eval;
+use crate::params::{EvalParams};
+use crate::pawns::{PawnTable};
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::iter::Peekable;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+
+pub const ENGINE_NAME: &str = "rustchess";
+pub const ENGINE_AUTHOR: &str = "scriptus_longus";
+
+const GO_PARAMETERS: [&str; 12] = ["searchmoves", "ponder", "wtime", "btime", "winc", "binc", "movestogo",
+                                   "depth", "nodes", "mate", "movetime", "infinite"];
+
+const COMMANDS: [&str; 12] = ["uci", "debug", "isready", "setoption", "register", "ucinewgame",
+                              "position", "go", "stop", "ponderhit", "quit", "eval"];
+
+pub struct Uci<W: Write + Send + 'static> {
+  pub game: Game,
+  pub debug: bool,
+  // shared with the search thread, which prints the bestmove
+  out: Arc<Mutex<W>>,
+  // kept between searches, it is locked by the search thread while it runs
+  tt: Arc<Mutex<TranspositionTable>>,
+  // kept like the tt, the entries depend on the weights
+  pawn_table: Arc<Mutex<PawnTable>>,
+  // evaluation weights, replaced as a whole by setoption EvalFile
+  params: Arc<EvalParams>,
+  stop: Arc<AtomicBool>,
+  ponder: Arc<AtomicBool>,
+  // wakes a finished search that waits for stop or ponderhit before it sends the bestmove
+  wake: Arc<(Mutex<()>, Condvar)>,
+  worker: Option<JoinHandle<()>>,
+  infinite: bool,
+}
+
+impl<W: Write + Send + 'static> Uci<W> {
+  pub fn new(out: W) -> Self {
+    Uci {
+      game: Game::from_fen(START_FEN).unwrap(),
+      debug: false,
+      out: Arc::new(Mutex::new(out)),
+      tt: Arc::new(Mutex::new(TranspositionTable::default())),
+      pawn_table: Arc::new(Mutex::new(PawnTable::new())),
+      params: Arc::new(EvalParams::default()),
+      stop: Arc::new(AtomicBool::new(false)),
+      ponder: Arc::new(AtomicBool::new(false)),
+      wake: Arc::new((Mutex::new(()), Condvar::new())),
+      worker: None,
+      infinite: false,
+    }
+  }
+
+  // reads commands until `quit` or the end of input
+  pub fn run<R: BufRead>(&mut self, input: R) -> io::Result<()> {
+    for line in input.lines() {
+      if !self.handle(&line?)? {
+        return Ok(());
+      }
+    }
+
+    // a search that only ends on stop would never finish without more input
+    self.finish_search(false);
+    Ok(())
+  }
+
+  // returns false once the engine should quit
+  pub fn handle(&mut self, line: &str) -> io::Result<bool> {
+    let mut tokens = line.split_whitespace();
+
+    // unknown tokens in front of a command are skipped
+    let command = match tokens.by_ref().find(|t| COMMANDS.contains(t)) {
+      Some(x) => x,
+      None => {
+        if self.debug && !line.trim().is_empty() {
+          self.send(&format!("info string Unknown command: {}", line.trim()))?;
+        }
+        return Ok(true);
+      },
+    };
+
+    // everything that touches the game stops the running search first
+    if ["setoption", "ucinewgame", "position", "go"].contains(&command) {
+      self.finish_search(true);
+    }
+
+    match command {
+      "uci" => {
+        self.send(&format!("id name {}", ENGINE_NAME))?;
+        self.send(&format!("id author {}", ENGINE_AUTHOR))?;
+        self.send(&format!("option name Hash type spin default {} min 1 max {}", tt::DEFAULT_HASH_MB, tt::MAX_HASH_MB))?;
+        self.send("option name EvalFile type string default <empty>")?;
+        self.send("uciok")?;
+      },
+      "debug" => {
+        self.debug = match tokens.next() {
+          Some("on") => true,
+          Some("off") => false,
+          _ => !self.debug,
+        };
+      },
+      "isready" => {
+        self.send("readyok")?;
+      },
+      "setoption" => {
+        if let Err(x) = self.set_option(&mut tokens) {
+          self.send(&format!("info string Error: {}", x))?;
+        }
+      },
+      "ucinewgame" => {
+        self.game.load_fen(START_FEN).unwrap();
+        self.tt.lock().unwrap().clear();
+        self.pawn_table.lock().unwrap().clear();
+      },
+      "position" => {
+        if let Err(x) = self.position(&mut tokens) {
+          self.send(&format!("info string Error: {}", x))?;
+        }
+      },
+      "go" => self.go(&mut tokens)?,
+      "stop" => self.finish_search(true),
+      // the opponent played the expected move, the search goes on with the clock running
+      "ponderhit" => self.signal(&self.ponder, false),
+      "quit" => {
+        self.finish_search(true);
+        return Ok(false);
+      },
+      // not part of UCI, prints every term of the static evaluation
+      "eval" => {
+        for line in eval::breakdown(&self.game.state, &self.params).to_string().lines() {
+          self.send(line)?;
+        }
+      },
+      _ => (),
+    }
+
+    Ok(true)
+  }
+
+  fn send(&self, line: &str) -> io::Result<()> {
+    let mut out = self.out.lock().unwrap();
+    writeln!(out, "{}", line)?;
+    out.flush()
+  }
+
+  // the flag is set under the lock, so a search about to wait cannot miss the wake up
+  fn signal(&self, flag: &AtomicBool, value: bool) {
+    let (lock, wake) = &*self.wake;
+    let _guard = lock.lock().unwrap();
+    flag.store(value, Ordering::Relaxed);
+    wake.notify_all();
+  }
+
+  // waits for the search thread, searches that only end on stop or ponderhit are stopped
+  fn finish_search(&mut self, stop: bool) {
+    if let Some(worker) = self.worker.take() {
+      if stop || self.infinite || self.ponder.load(Ordering::Relaxed) {
+        self.signal(&self.stop, true);
+      }
+      let _ = worker.join();
+    }
+  }
+
+  fn set_option<'a, I>(&mut self, tokens: &mut I) -> Result<(), &'static str>
+  where
+    I: Iterator<Item = &'a str>,
+  {
+    if tokens.next() != Some("name") {
+      return Err("setoption expects a name");
+    }
+
+    // option names and values may contain spaces
+    let mut name = vec![];
+    for token in tokens.by_ref() {
+      if token == "value" {
+        break;
+      }
+      name.push(token);
+    }
+
+    if name.is_empty() {
+      return Err("setoption expects a name");
+    }
+    let value = tokens.collect::<Vec<&str>>().join(" ");
+
+    // option names are not case sensitive
+    match name.join(" ").to_lowercase().as_str() {
+      "hash" => {
+        let mb = match value.parse::<usize>() {
+          Ok(x) if (1..=tt::MAX_HASH_MB).contains(&x) => x,
+          _ => return Err("Hash must be a size in MB between 1 and 1024"),
+        };
+        self.tt.lock().unwrap().resize(mb);
+      },
+      // an empty value goes back to the built in weights
+      "evalfile" => {
+        self.params = match value.as_str() {
+          "" | "<empty>" => Arc::new(EvalParams::default()),
+          path => Arc::new(EvalParams::load(path)?),
+        };
+        // values of the old weights must not be reused
+        self.tt.lock().unwrap().clear();
+        self.pawn_table.lock().unwrap().clear();
+      },
+      _ => return Err("Unknown option"),
+    }
+
+    Ok(())
+  }
+
+  fn position<'a, I>(&mut self, tokens: &mut I) -> Result<(), &'static str>
+  where
+    I: Iterator<Item = &'a str>,
+  {
+    // the moves are played on a copy, a bad command leaves the position as it was
+    let mut game = match tokens.next() {
+      Some("startpos") => {
+        match tokens.next() {
+          Some("moves") | None => (),
+          _ => return Err("Expected moves after startpos"),
+        }
+        Game::from_fen(START_FEN)?
+      },
+      Some("fen") => {
+        let fields: Vec<&str> = tokens.by_ref().take_while(|t| *t != "moves").collect();
+
+        // halfmove and fullmove clocks are optional
+        let fen = match fields.len() {
+          4 => format!("{} 0 1", fields.join(" ")),
+          6 => fields.join(" "),
+          _ => return Err("FEN must have 4 or 6 fields"),
+        };
+
+        Game::from_fen(&fen)?
+      },
+      _ => return Err("Position must be startpos or fen"),
+    };
+
+    for lan in tokens {
+      let m = Move::from_lan(lan, &game.state)?;
+
+      if !game.state.is_legal_move(&m) || !game.moves().contains(&m) {
+        return Err("Illegal move in position command");
+      }
+      game.makemove(&m)?;
+    }
+
+    self.game = game;
+    Ok(())
+  }
+
+  fn parse_go<'a, I>(&self, tokens: &mut Peekable<I>) -> Result<SearchLimits, &'static str>
+  where
+    I: Iterator<Item = &'a str>,
+  {
+    let mut limits = SearchLimits::default();
+
+    while let Some(token) = tokens.next() {
+      match token {
+        // negative clock times are sent by some GUIs once the flag has fallen
+        "wtime" => limits.wtime = Some(parse_number::<i64>(tokens.next())?.max(0) as u64),
+        "btime" => limits.btime = Some(parse_number::<i64>(tokens.next())?.max(0) as u64),
+        "winc" => limits.winc = Some(parse_number(tokens.next())?),
+        "binc" => limits.binc = Some(parse_number(tokens.next())?),
+        "movestogo" => limits.movestogo = Some(parse_number(tokens.next())?),
+        "movetime" => limits.movetime = Some(parse_number(tokens.next())?),
+        "depth" => limits.depth = Some(parse_number(tokens.next())?),
+        "nodes" => limits.nodes = Some(parse_number(tokens.next())?),
+        "mate" => limits.mate = Some(parse_number(tokens.next())?),
+        "infinite" => limits.infinite = true,
+        "ponder" => limits.ponder = true,
+        "searchmoves" => {
+          while let Some(lan) = tokens.next_if(|t| !GO_PARAMETERS.contains(t)) {
+            let m = Move::from_lan(lan, &self.game.state)?;
+            if !self.game.state.is_legal_move(&m) || !self.game.moves().contains(&m) {
+              return Err("Illegal move in searchmoves");
+            }
+            limits.searchmoves.push(m);
+          }
+        },
+        _ => return Err("Unknown go parameter"),
+      }
+    }
+
+    Ok(limits)
+  }
+
+  fn go<'a, I>(&mut self, tokens: &mut I) -> io::Result<()>
+  where
+    I: Iterator<Item = &'a str>,
+  {
+    let mut tokens = tokens.peekable();
+
+    if tokens.next_if_eq(&"perft").is_some() {
+      let depth = tokens.next().and_then(|d| d.parse().ok()).unwrap_or(1);
+      let counts = perft::debug_perft(&mut self.game, depth);
+
+      // the nodes per move are only listed in debug mode
+      if self.debug && !counts.is_empty() {
+        for (m, n) in counts.iter() {
+          let lan = Move::to_lan(m, &self.game.state).unwrap_or_else(|_| String::from("Error decoding lan"));
+          self.send(&format!("{}: {}", lan, n))?;
+        }
+        self.send("")?;
+      }
+
+      // a depth of zero counts the position itself
+      let n = if depth == 0 { 1 } else { counts.iter().map(|(_, n)| n).sum() };
+      return self.send(&format!("Nodes searched: {}", n));
+    }
+
+    let limits = match self.parse_go(&mut tokens) {
+      Ok(x) => x,
+      Err(x) => return self.send(&format!("info string Error: {}", x)),
+    };
+
+    self.infinite = limits.infinite;
+    self.stop.store(false, Ordering::Relaxed);
+    self.ponder.store(limits.ponder, Ordering::Relaxed);
+
+    let mut game = self.game.clone();
+    let out = self.out.clone();
+    let stop = self.stop.clone();
+    let ponder = self.ponder.clone();
+    let wake = self.wake.clone();
+    let tt = self.tt.clone();
+    let pawn_table = self.pawn_table.clone();
+    let params = self.params.clone();
+    let debug = self.debug;
+
+    self.worker = Some(thread::spawn(move || {
+      let infinite = limits.infinite;
+      let root = game.state;
+
+      let mut tt = tt.lock().unwrap();
+      let mut pawn_table = pawn_table.lock().unwrap();
+      let mut searcher = Searcher::new(limits, &mut tt, &mut pawn_table, stop.clone(), ponder.clone());
+      searcher.set_params(params);
+      let (pv, _) = searcher.go(&mut game, |info| {
+        let mut out = out.lock().unwrap();
+        let _ = writeln!(out, "{}", info_line(info, &root)).and_then(|_| out.flush());
+        if debug {
+          let rate = info.first_move_cutoffs * 100.0;
+          let _ = writeln!(out, "info string first move cutoffs {:.1}%", rate).and_then(|_| out.flush());
+        }
+      });
+
+      // the bestmove of an infinite or ponder search is only sent after stop or ponderhit
+      let (lock, cvar) = &*wake;
+      let mut guard = lock.lock().unwrap();
+      while (infinite || ponder.load(Ordering::Relaxed)) && !stop.load(Ordering::Relaxed) {
+        guard = cvar.wait(guard).unwrap();
+      }
+      drop(guard);
+
+      // the second move of the pv is the one to ponder on
+      let line = match lan_line(&pv, &root).as_slice() {
+        [] => String::from("bestmove 0000"),
+        [best] => format!("bestmove {}", best),
+        [best, ponder, ..] => format!("bestmove {} ponder {}", best, ponder),
+      };
+
+      let mut out = out.lock().unwrap();
+      let _ = writeln!(out, "{}", line).and_then(|_| out.flush());
+    }));
+
+    Ok(())
+  }
+}
+
+impl<W: Write + Send + 'static> Drop for Uci<W> {
+  fn drop(&mut self) {
+    self.finish_search(true);
+  }
+}
+
+// moves of a line are converted on a copy of the root position
+fn lan_line(moves: &[Move], root: &GameState) -> Vec<String> {
+  let mut state = *root;
+  let mut ret = vec![];
+
+  for m in moves {
+    match Move::to_lan(m, &state) {
+      Ok(x) => ret.push(x),
+      Err(_) => break,
+    }
+    state.make_move(m);
+  }
+
+  ret
+}
+
+// UCI scores are from the point of view of the engine
+fn info_line(info: &SearchInfo, root: &GameState) -> String {
+  let score = match root.get_player() {
+    Player::White => info.value,
+    Player::Black => -info.value,
+  };
+
+  let ms = info.time.as_millis() as u64;
+  let nps = info.nodes * 1000 / ms.max(1);
+
+  let mut line = format!("info depth {} seldepth {} score {} nodes {} nps {} time {}",
+                         info.depth, info.seldepth, score, info.nodes, nps, ms);
+
+  let pv = lan_line(&info.pv, root);
+  if !pv.is_empty() {
+    line.push_str(&format!(" pv {}", pv.join(" ")));
+  }
+
+  line
+}
+
+fn parse_number<T: FromStr>(token: Option<&str>) -> Result<T, &'static str> {
+  match token.map(|t| t.parse()) {
+    Some(Ok(x)) => Ok(x),
+    _ => Err("go parameter expects a number"),
+  }
+}
@@ -0,0 +1,79 @@
+use crate::board::{Player, Pieces};
+
+// keys are generated at compile time from a fixed seed so hashes are stable between runs
+pub struct ZobristKeys {
+  pub pieces: [[[u64; 64]; 6]; 2],
+  pub side: u64,
+  pub castling: [u64; 16],
+  pub ep_file: [u64; 8],
+}
+
+const fn splitmix64(state: &mut u64) -> u64 {
+  *state = state.wrapping_add(0x9e3779b97f4a7c15);
+
+  let mut z = *state;
+  z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+  z ^ (z >> 31)
+}
+
+const fn generate_keys() -> ZobristKeys {
+  let mut state = 0x5eed_c0ff_ee15_600du64;
+  let mut keys = ZobristKeys {pieces: [[[0u64; 64]; 6]; 2], side: 0, castling: [0u64; 16], ep_file: [0u64; 8]};
+
+  let mut player = 0;
+  while player < 2 {
+    let mut piece = 0;
+    while piece < 6 {
+      let mut sq = 0;
+      while sq < 64 {
+        keys.pieces[player][piece][sq] = splitmix64(&mut state);
+        sq += 1;
+      }
+      piece += 1;
+    }
+    player += 1;
+  }
+
+  keys.side = splitmix64(&mut state);
+
+  let mut i = 0;
+  while i < 16 {
+    keys.castling[i] = splitmix64(&mut state);
+    i += 1;
+  }
+
+  i = 0;
+  while i < 8 {
+    keys.ep_file[i] = splitmix64(&mut state);
+    i += 1;
+  }
+
+  keys
+}
+
+pub static ZOBRIST: ZobristKeys = generate_keys();
+
+// squares are absolute (white's point of view), not relative to the player to move
+#[inline]
+pub fn piece_key(player: Player, piece: Pieces, shift: u32) -> u64 {
+  ZOBRIST.pieces[player as usize][piece as usize][shift as usize]
+}
+
+#[inline]
+pub fn side_key() -> u64 {
+  ZOBRIST.side
+}
+
+#[inline]
+pub fn castling_key(castling: u8) -> u64 {
+  ZOBRIST.castling[(castling & 0xf) as usize]
+}
+
+#[inline]
+pub fn ep_key(ep_square: Option<u32>) -> u64 {
+  match ep_square {
+    Some(sq) => ZOBRIST.ep_file[(sq % 8) as usize],
+    None => 0,
+  }
+}
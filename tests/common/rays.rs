@@ -0,0 +1,81 @@
+// the ray walk movegen used before the magic tables, kept as the reference for the lookups
+
+pub enum Dir {
+  U,
+  D,
+  R,
+  L
+}
+
+#[derive(PartialEq)]
+pub enum DiagDir {
+  NE,
+  NW,
+  SE,
+  SW
+}
+
+fn ray_mask(from: u32, blocker_mask: u64, direction: Dir) -> u64 {
+  let mut ret = 0;
+  let st_size: i32 = match direction {
+    Dir::U => 8,
+    Dir::D => -8,
+    Dir::L => -1,
+    Dir::R => 1,
+  };
+
+  let start_rank: i32 = (from as i32) / 8;
+  let start_file: i32 = (from as i32) % 8;
+
+  let mut sq = (from as i32) + st_size;
+  while (0..64).contains(&sq) && (sq >> 3 == start_rank || sq & 7 == start_file)  {
+    let to_mask = 1u64 << sq;
+
+    ret |= to_mask;
+    sq += st_size;
+
+    if to_mask & blocker_mask != 0 {
+      break;
+    }
+  }
+
+  ret
+}
+
+fn diag_ray_mask(from: u32, blocker_mask: u64, direction: DiagDir) -> u64 {
+  let mut ret = 0;
+
+  let st_size: i32 = match direction {
+    DiagDir::NE => 9,
+    DiagDir::NW => 7,
+    DiagDir::SE => -7,
+    DiagDir::SW => -9,
+  };
+
+  let mut sq:i32 = from as i32 + st_size;
+
+  // ugly condition but it works
+  while (0..64).contains(&sq) && ((sq as u32 & 7) > (from & 7) && (direction == DiagDir::NE || direction == DiagDir::SE) ||
+                                 (sq as u32 & 7) < (from & 7) && (direction == DiagDir::NW || direction == DiagDir::SW) )  {
+    let to_mask = 1u64 << sq;
+
+    ret |= to_mask;
+    sq += st_size;
+
+    if to_mask & blocker_mask != 0 {
+      break;
+    }
+  }
+
+  ret
+}
+
+pub fn rook_rays(from: u32, occupied: u64) -> u64 {
+  ray_mask(from, occupied, Dir::U) | ray_mask(from, occupied, Dir::D) |
+    ray_mask(from, occupied, Dir::L) | ray_mask(from, occupied, Dir::R)
+}
+
+pub fn bishop_rays(from: u32, occupied: u64) -> u64 {
+  diag_ray_mask(from, occupied, DiagDir::NE) | diag_ray_mask(from, occupied, DiagDir::NW) |
+    diag_ray_mask(from, occupied, DiagDir::SE) | diag_ray_mask(from, occupied, DiagDir::SW)
+}
@@ -0,0 +1,160 @@
+use rustchess::game::{GameState};
+use rustchess::eval::{eval, breakdown, game_phase, king_safety, mobility, piece_activity, Term, TERMS, MAX_PHASE};
+use rustchess::board::{Player};
+use rustchess::params::{DEFAULT_PARAMS};
+use rustchess::score::{Score};
+
+// the same position with the colors swapped and the board turned upside down
+fn mirror_fen(fen: &str) -> String {
+  let fields: Vec<&str> = fen.split(' ').collect();
+  let swap_case = |s: &str| s.chars()
+    .map(|c| if c.is_uppercase() { c.to_ascii_lowercase() } else { c.to_ascii_uppercase() })
+    .collect::<String>();
+
+  let board = fields[0].split('/').rev().map(swap_case).collect::<Vec<_>>().join("/");
+  let active = if fields[1] == "w" { "b" } else { "w" };
+  let ep = match fields[3] {
+    "-" => "-".to_string(),
+    sq => format!("{}{}", &sq[..1], if &sq[1..] == "3" { "6" } else { "3" }),
+  };
+
+  format!("{} {} {} {} {} {}", board, active, swap_case(fields[2]), ep, fields[4], fields[5])
+}
+
+#[cfg(test)]
+mod eval_test {
+  use super::*;
+
+  #[test]
+  pub fn test_startpos() {
+    let s = GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    assert_eq!(eval(&s), Score::ZERO);
+    assert_eq!(game_phase(&s), MAX_PHASE);
+  }
+
+  #[test]
+  pub fn test_symmetry() {
+    let fens = ["r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+                "4k3/8/8/3p4/8/8/4P3/4K3 b - - 0 1"];
+
+    for fen in fens {
+      let s = GameState::from_fen(fen).unwrap();
+      let mirrored = GameState::from_fen(&mirror_fen(fen)).unwrap();
+      assert_eq!(eval(&s), -eval(&mirrored), "{}", fen);
+    }
+  }
+
+  #[test]
+  pub fn test_side_to_move() {
+    // the relative board is flipped for black, the eval must still see the same position
+    let white = GameState::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+    let black = GameState::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1").unwrap();
+    assert_eq!(eval(&white), eval(&black));
+    assert!(eval(&white) != Score::ZERO);
+  }
+
+  #[test]
+  pub fn test_tapered() {
+    // pawn endings are evaluated with the endgame tables only
+    let center = GameState::from_fen("8/8/4k3/8/3K4/8/4P3/8 w - - 0 1").unwrap();
+    let corner = GameState::from_fen("8/8/4k3/8/8/8/4P3/K7 w - - 0 1").unwrap();
+    assert_eq!(game_phase(&center), 0);
+    assert!(eval(&center) > eval(&corner));
+
+    // with all pieces on the board the king belongs behind its pawns
+    let castled = GameState::from_fen("r1bq1rk1/pppp1ppp/2n2n2/2b1p3/2B1P3/2N2N2/PPPP1PPP/R1BQ1RK1 w - - 0 1").unwrap();
+    let center = GameState::from_fen("r1bq1rk1/pppp1ppp/2n2n2/2b1p3/2B1P3/2NK1N2/PPPP1PPP/R1BQ1R2 w - - 0 1").unwrap();
+    assert_eq!(game_phase(&castled), MAX_PHASE);
+    assert!(eval(&castled) > eval(&center));
+  }
+
+  #[test]
+  pub fn test_king_safety() {
+    let safety = |fen: &str, player| king_safety(&GameState::from_fen(fen).unwrap().get_absolute_board(), player, &DEFAULT_PARAMS).0;
+
+    // the same pawns in front of the king or on the other wing
+    let shield = safety("6k1/8/8/8/8/8/5PPP/6K1 w - - 0 1", Player::White);
+    assert!(shield > safety("6k1/8/8/8/8/8/PPP5/6K1 w - - 0 1", Player::White));
+    assert_eq!(shield, safety("6k1/5ppp/8/8/8/8/8/6K1 w - - 0 1", Player::Black));
+
+    // enemy pawns close to the king, or an open file next to it
+    assert!(safety("6k1/8/8/8/8/6p1/5P1P/6K1 w - - 0 1", Player::White) < safety("6k1/8/8/8/8/8/5P1P/6K1 w - - 0 1", Player::White));
+    assert!(safety("6k1/8/8/8/8/8/5P1P/6K1 w - - 0 1", Player::White) < shield);
+
+    // a single attacker is ignored, the queen and the rook together are dangerous
+    assert_eq!(safety("6k1/8/8/8/7q/8/5PPP/6K1 w - - 0 1", Player::White), shield);
+    assert!(safety("6k1/8/8/6r1/7q/8/5PPP/6K1 w - - 0 1", Player::White) < shield - 50);
+  }
+
+  #[test]
+  pub fn test_mobility() {
+    let white = |fen: &str| mobility(&GameState::from_fen(fen).unwrap().get_absolute_board(), Player::White, &DEFAULT_PARAMS);
+
+    let center = white("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1");
+    assert!(center.0 > white("4k3/8/8/8/8/8/8/N3K3 w - - 0 1").0);
+    assert!(center.1 > white("4k3/8/8/8/8/8/8/N3K3 w - - 0 1").1);
+
+    // c6 and e6 are covered by the pawn on d7, the one on e7 does not take anything away
+    assert_eq!(center, white("4k3/4p3/8/8/3N4/8/8/4K3 w - - 0 1"));
+    assert!(white("4k3/3p4/8/8/3N4/8/8/4K3 w - - 0 1").0 < center.0);
+  }
+
+  #[test]
+  pub fn test_piece_activity() {
+    let white = |fen: &str| piece_activity(&GameState::from_fen(fen).unwrap().get_absolute_board(), Player::White, &DEFAULT_PARAMS);
+
+    assert!(white("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1").1 > white("4k3/8/8/8/8/8/8/2B1KN2 w - - 0 1").1);
+
+    // open file, semi-open file and closed file
+    let open = white("4k3/p7/8/8/8/8/P7/3RK3 w - - 0 1");
+    let semi_open = white("4k3/3p4/8/8/8/8/P7/3RK3 w - - 0 1");
+    let closed = white("4k3/8/8/8/8/8/3P4/3RK3 w - - 0 1");
+    assert!(open.0 > semi_open.0 && semi_open.0 > closed.0);
+
+    // the 7th rank with the enemy king cut off on the 8th
+    assert!(white("4k3/3R4/8/8/8/8/8/4K3 w - - 0 1").1 > white("4k3/8/3R4/8/8/8/8/4K3 w - - 0 1").1);
+    assert_eq!(white("8/3R4/4k3/8/8/8/8/4K3 w - - 0 1"), white("8/8/3R4/4k3/8/8/8/4K3 w - - 0 1"));
+
+    // e5 can not be chased away by a pawn any more once f7 is gone
+    assert!(white("4k3/8/8/4N3/3P4/8/8/4K3 w - - 0 1").0 > white("4k3/5p2/8/4N3/3P4/8/8/4K3 w - - 0 1").0);
+
+    // the king has left e1 but did not castle, the rook is stuck behind it
+    assert!(white("4k3/8/8/8/8/8/6PP/5K1R w - - 0 1").0 < white("4k3/8/8/8/8/8/6PP/4K2R w - - 0 1").0);
+    assert!(white("4k3/B7/1p6/8/8/8/8/4K3 w - - 0 1").0 < white("4k3/B7/8/1p6/8/8/8/4K3 w - - 0 1").0);
+  }
+
+  #[test]
+  pub fn test_breakdown() {
+    let fens = ["r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 0 1"];
+
+    for fen in fens {
+      let s = GameState::from_fen(fen).unwrap();
+      let b = breakdown(&s, &DEFAULT_PARAMS);
+      assert_eq!(b.total(), eval(&s));
+      assert_eq!(b.phase, game_phase(&s));
+      assert_eq!(b.get(Term::KingSafety, Player::Black), king_safety(&s.get_absolute_board(), Player::Black, &DEFAULT_PARAMS));
+    }
+
+    // the two bishops only show up in the activity of white, the passer only in the pawns
+    let b = breakdown(&GameState::from_fen("2n1kn2/pp4pp/8/3P4/4P3/8/PP4PP/2B1KB2 w - - 0 1").unwrap(), &DEFAULT_PARAMS);
+    assert!(b.diff(Term::Activity).1 > 0);
+    assert!(b.diff(Term::Pawns).1 > 0);
+    assert!(b.get(Term::Pawns, Player::Black) < b.get(Term::Pawns, Player::White));
+
+    // a lone extra knight is pure material and piece-square value
+    let b = breakdown(&GameState::from_fen("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1").unwrap(), &DEFAULT_PARAMS);
+    for term in TERMS {
+      if term != Term::Material && term != Term::PieceSquare && term != Term::Mobility {
+        assert_eq!(b.diff(term), (0, 0), "{}", term.name());
+      }
+    }
+    assert!(b.diff(Term::Material).0 > 300);
+
+    let table = b.to_string();
+    assert!(table.lines().any(|l| l.trim_start().starts_with("material")));
+    assert!(table.lines().any(|l| l.trim_start().starts_with("total") && l.ends_with(&b.total().0.to_string())));
+  }
+}
@@ -0,0 +1,82 @@
+use rustchess::game::{Game, GameState};
+use rustchess::board::{Board};
+use rustchess::movegen::{Move};
+
+#[cfg(test)]
+mod fen_test {
+  use super::*;
+
+  const FENS: [&str; 9] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "b6n/5Pp1/3B1bp1/5p1p/1P3Pk1/1P4r1/1Q6/1K6 w - - 0 1",
+    "2N1nB2/qk1K4/1bpp4/4P1PB/1p3Pp1/Q7/8/8 w - - 0 1",
+    "5b2/P2P4/1Q4r1/7P/pP2B3/1n2Kn1p/k2p3P/8 b - - 0 1",
+    "8/P3p3/4P3/B7/1q6/1Pp3K1/1k6/8 b - - 0 1",
+  ];
+
+  #[test]
+  pub fn test_board_round_trip() {
+    for fen in FENS {
+      let placement = fen.split(" ").next().unwrap();
+      let board = Board::from_fen(placement).unwrap();
+
+      assert_eq!(board.to_fen(), placement);
+    }
+  }
+
+  #[test]
+  pub fn test_state_round_trip() {
+    for fen in FENS {
+      let state = GameState::from_fen(fen).unwrap();
+
+      assert_eq!(state.to_fen(), fen);
+    }
+  }
+
+  #[test]
+  pub fn test_en_passant_round_trip() {
+    for fen in ["rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+                "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2"] {
+      let state = GameState::from_fen(fen).unwrap();
+
+      assert_eq!(state.to_fen(), fen);
+    }
+  }
+
+  #[test]
+  pub fn test_invalid_fens() {
+    assert!(GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1").is_err());
+    assert!(GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 -1").is_err());
+    assert!(GameState::from_fen("8/8/8/8/8/8/8/8 w - - 0 1").is_err());
+    assert!(GameState::from_fen("4k3/8/8/8/8/8/8/8 w - - 0 1").is_err());
+    assert!(GameState::from_fen("4k3/8/8/8/8/8/8/3KK3 w - - 0 1").is_err());
+
+    // a failed load keeps the game usable
+    let mut game = Game::from_fen(FENS[1]).unwrap();
+    assert!(game.load_fen("8/8/8/8/8/8/8/8 w - - 0 1").is_err());
+    assert_eq!(game.state.to_fen(), FENS[1]);
+    assert!(!game.legal_moves().is_empty());
+  }
+
+  #[test]
+  pub fn test_fen_after_moves() {
+    let mut game = Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+
+    for (lan, fen) in [("e2e4", "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+                       ("g8f6", "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 2"),
+                       ("e4e5", "rnbqkb1r/pppppppp/5n2/4P3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2"),
+                       ("d7d5", "rnbqkb1r/ppp1pppp/5n2/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3"),
+                       ("e5d6", "rnbqkb1r/ppp1pppp/3P1n2/8/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3"),
+                       ("h8g8", "rnbqkbr1/ppp1pppp/3P1n2/8/8/8/PPPP1PPP/RNBQKBNR w KQq - 1 4")] {
+      let m = Move::from_lan(lan, &game.state).unwrap();
+      game.makemove(&m).unwrap();
+
+      assert_eq!(game.state.to_fen(), fen);
+      assert_eq!(GameState::from_fen(fen).unwrap().to_fen(), fen);
+    }
+  }
+}
@@ -0,0 +1,131 @@
+use rustchess::game::{Game, GameResult, WinReason, RemisReason};
+use rustchess::board::{Player};
+use rustchess::movegen::{Move};
+
+#[cfg(test)]
+mod game_test {
+  use super::*;
+
+  fn play(game: &mut Game, moves: &[&str]) {
+    for lan in moves {
+      let m = Move::from_lan(lan, &game.state).unwrap();
+      game.makemove(&m).unwrap();
+    }
+  }
+
+  #[test]
+  pub fn test_threefold_repetition() {
+    let mut game = Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+
+    play(&mut game, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+    assert!(!game.is_threefold_repetition());
+
+    play(&mut game, &["g1f3", "g8f6", "f3g1"]);
+    assert!(!game.is_threefold_repetition());
+
+    play(&mut game, &["f6g8"]);
+    assert!(game.is_threefold_repetition());
+    assert!(game.is_remis());
+
+    game.undo_move();
+    assert!(!game.is_threefold_repetition());
+  }
+
+  #[test]
+  pub fn test_repetition_needs_same_rights() {
+    // the first king move loses castling rights, so the start position never repeats
+    let mut game = Game::from_fen("r3k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+
+    play(&mut game, &["e1f1", "e8d8", "f1e1", "d8e8", "e1f1", "e8d8", "f1e1", "d8e8"]);
+    assert!(!game.is_threefold_repetition());
+
+    play(&mut game, &["e1f1", "e8d8", "f1e1", "d8e8"]);
+    assert!(game.is_threefold_repetition());
+  }
+
+  #[test]
+  pub fn test_insufficient_material() {
+    for (fen, expected) in [("8/8/4k3/8/8/3K4/8/8 w - - 0 1", true),
+                            ("8/8/4k3/8/8/3K4/3N4/8 w - - 0 1", true),
+                            ("8/8/4k3/8/8/3K4/3B4/8 b - - 0 1", true),
+                            ("8/8/4k3/4b3/8/3K4/3B4/8 w - - 0 1", true),
+                            ("8/8/4k3/3b4/8/3K4/3B4/8 w - - 0 1", false),
+                            ("8/8/4k3/4n3/8/3K4/3B4/8 w - - 0 1", false),
+                            ("8/8/4k3/8/8/3K4/2NN4/8 w - - 0 1", false),
+                            ("8/8/4k3/8/8/3K4/3P4/8 w - - 0 1", false),
+                            ("8/8/4k3/8/8/3K4/3R4/8 b - - 0 1", false)] {
+      let game = Game::from_fen(fen).unwrap();
+
+      assert_eq!(game.is_insufficient_material(), expected, "Failed for {}", fen);
+    }
+  }
+
+  #[test]
+  pub fn test_result_reasons() {
+    for (fen, expected) in [("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3", GameResult::Win(Player::Black, WinReason::Checkmate)),
+                            ("6k1/5ppp/8/8/8/8/8/3R2K1 b - - 0 1", GameResult::NotDone),
+                            ("3R2k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", GameResult::Win(Player::White, WinReason::Checkmate)),
+                            ("7k/5Q2/8/8/8/8/8/6K1 b - - 0 1", GameResult::Remis(RemisReason::Stalemate)),
+                            ("7k/8/8/8/8/8/8/5NK1 w - - 0 1", GameResult::Remis(RemisReason::InsufficientMaterial)),
+                            ("7k/8/8/8/8/8/8/5RK1 w - - 99 80", GameResult::NotDone),
+                            ("7k/8/8/8/8/8/8/5RK1 w - - 100 80", GameResult::Remis(RemisReason::FiftyMoveRule))] {
+      let mut game = Game::from_fen(fen).unwrap();
+
+      assert_eq!(game.get_result(), expected, "Failed for {}", fen);
+    }
+  }
+
+  #[test]
+  pub fn test_repetition_result() {
+    let mut game = Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+
+    play(&mut game, &["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8"]);
+    assert_eq!(game.get_result(), GameResult::Remis(RemisReason::ThreefoldRepetition));
+  }
+
+  #[test]
+  pub fn test_repetition_after_double_push() {
+    // no white pawn can take on d6, so the position right after d5 is the one that comes back
+    let mut game = Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+
+    play(&mut game, &["e2e4", "d7d5", "g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1"]);
+    assert!(!game.is_threefold_repetition());
+
+    play(&mut game, &["f6g8"]);
+    assert!(game.is_threefold_repetition());
+    assert_eq!(game.get_result(), GameResult::Remis(RemisReason::ThreefoldRepetition));
+  }
+
+  #[test]
+  pub fn test_adjudicated_results() {
+    let mut game = Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    assert_eq!(game.get_result(), GameResult::NotDone);
+
+    game.resign(Player::White);
+    assert_eq!(game.get_result(), GameResult::Win(Player::Black, WinReason::Resignation));
+
+    game.lose_on_time(Player::Black);
+    assert_eq!(game.get_result(), GameResult::Win(Player::White, WinReason::Timeout));
+
+    game.agree_remis();
+    assert_eq!(game.get_result(), GameResult::Remis(RemisReason::Agreement));
+
+    game.load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    assert_eq!(game.get_result(), GameResult::NotDone);
+  }
+
+  #[test]
+  pub fn test_undo_takes_back_adjudication() {
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/4r3/3RK3 w - - 0 1").unwrap();
+    play(&mut game, &["e1e2"]);
+    game.resign(Player::Black);
+
+    // an illegal move is undone at once and does not touch the result
+    let m = Move::from_lan("e8d8", &game.state).unwrap();
+    assert!(game.makemove(&m).is_err());
+    assert_eq!(game.get_result(), GameResult::Win(Player::White, WinReason::Resignation));
+
+    game.undo_move();
+    assert_eq!(game.get_result(), GameResult::NotDone);
+  }
+}
@@ -0,0 +1,45 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::{StdRng};
+
+use rustchess::magic;
+
+#[path = "common/rays.rs"]
+mod rays;
+use rays::{rook_rays, bishop_rays};
+
+#[cfg(test)]
+mod magic_test {
+  use super::*;
+
+  #[test]
+  pub fn test_empty_and_full() {
+    for sq in 0..64 {
+      assert_eq!(magic::rook_attacks(sq, 0), rook_rays(sq, 0), "square {}", sq);
+      assert_eq!(magic::bishop_attacks(sq, 0), bishop_rays(sq, 0), "square {}", sq);
+      assert_eq!(magic::rook_attacks(sq, !0), rook_rays(sq, !0), "square {}", sq);
+      assert_eq!(magic::bishop_attacks(sq, !0), bishop_rays(sq, !0), "square {}", sq);
+    }
+
+    // a rook in a corner sees the whole rank and file
+    assert_eq!(magic::rook_attacks(0, 0).count_ones(), 14);
+    assert_eq!(magic::bishop_attacks(27, 0).count_ones(), 13);
+  }
+
+  #[test]
+  pub fn test_random_occupancy() {
+    let mut rng = StdRng::seed_from_u64(0x6d61676963);
+
+    for _ in 0..20_000 {
+      // sparse and dense boards, the piece itself may or may not be part of the occupancy
+      let occupied = match rng.random_range(0..3) {
+        0 => rng.random::<u64>() & rng.random::<u64>() & rng.random::<u64>(),
+        1 => rng.random::<u64>() & rng.random::<u64>(),
+        _ => rng.random::<u64>(),
+      };
+      let sq = rng.random_range(0..64);
+
+      assert_eq!(magic::rook_attacks(sq, occupied), rook_rays(sq, occupied), "square {} occupied {:#x}", sq, occupied);
+      assert_eq!(magic::bishop_attacks(sq, occupied), bishop_rays(sq, occupied), "square {} occupied {:#x}", sq, occupied);
+    }
+  }
+}
@@ -1,5 +1,9 @@
 use rustchess::board::{Pieces, Player, Board};
 use rustchess::movegen::{MoveGen, Move};
+use rustchess::game::{Game};
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::{StdRng};
 
 #[cfg(test)]
 mod movegen_test {
@@ -154,4 +158,170 @@ mod movegen_test {
       
     }
   }
+
+  #[test]
+  fn test_san_round_trip() {
+    for fen in ["r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                "5b2/P2P4/1Q4r1/7P/pP2B3/1n2Kn1p/k2p3P/8 b - - 0 1",
+                "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2"] {
+      let mut game = Game::from_fen(fen).unwrap();
+
+      for m in game.legal_moves() {
+        let san = Move::to_san(&m, &mut game).unwrap();
+        assert_eq!(Move::from_san(&san, &game.state), Ok(m), "Failed for {} in {}", san, fen);
+      }
+    }
+  }
+
+  #[test]
+  fn test_san_format() {
+    for (fen, lan, san) in [("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", "e1g1", "O-O"),
+                            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1", "e8c8", "O-O-O"),
+                            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", "e5f7", "Nxf7"),
+                            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", "d5e6", "dxe6"),
+                            ("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2", "e5d6", "exd6"),
+                            ("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q", "a8=Q+"),
+                            ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8", "Ra8#"),
+                            ("3k4/8/8/8/8/8/4K3/R2n3R w - - 0 1", "a1d1", "Raxd1+"),
+                            ("8/8/1k6/8/Q7/8/8/Q2QK3 w - - 0 1", "a1d4", "Qa1d4+"),
+                            ("8/8/1k6/8/Q7/8/8/Q3K2Q w - - 0 1", "a1d4", "Q1d4+"),
+                            ("3k4/8/8/8/8/8/4K3/R6R w - - 0 1", "h1f1", "Rhf1"),
+                            ("7k/8/8/R7/8/8/8/R3K3 w - - 0 1", "a5a3", "R5a3")] {
+      let mut game = Game::from_fen(fen).unwrap();
+      let m = Move::from_lan(lan, &game.state).unwrap();
+
+      assert_eq!(Move::to_san(&m, &mut game), Ok(String::from(san)), "Failed for {}", fen);
+      assert_eq!(Move::from_san(san, &game.state), Ok(m), "Failed for {}", fen);
+    }
+  }
+
+  #[test]
+  fn test_san_errors() {
+    let game = Game::from_fen("8/8/1k6/8/Q7/8/8/Q3K2Q w - - 0 1").unwrap();
+
+    assert_eq!(Move::from_san("Qd4", &game.state), Err("Move is ambiguous"));
+    assert_eq!(Move::from_san("Qad4", &game.state), Err("Move is ambiguous"));
+    assert_eq!(Move::from_san("Ke3", &game.state), Err("Move is illegal in this position"));
+    assert_eq!(Move::from_san("O-O", &game.state), Err("Move is illegal in this position"));
+    assert_eq!(Move::from_san("Qxd4", &game.state), Err("Move is marked as capture but target square is empty"));
+    assert!(Move::from_san("Qz9", &game.state).is_err());
+    assert!(Move::from_san("", &game.state).is_err());
+  }
+
+  #[test]
+  fn test_lan_round_trip() {
+    for fen in ["r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                "5b2/P2P4/1Q4r1/7P/pP2B3/1n2Kn1p/k2p3P/8 b - - 0 1",
+                "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+                "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b KQkq d3 0 2"] {
+      let mut game = Game::from_fen(fen).unwrap();
+
+      for m in game.legal_moves() {
+        let lan = Move::to_lan(&m, &game.state).unwrap();
+        assert_eq!(Move::from_lan(&lan, &game.state), Ok(m), "Failed for {} in {}", lan, fen);
+      }
+    }
+  }
+
+  #[test]
+  fn test_lan_promotions() {
+    let mut game = Game::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let mut lans: Vec<String> = game.legal_moves().iter()
+      .filter(|m| m.promotion.is_some())
+      .map(|m| Move::to_lan(m, &game.state).unwrap())
+      .collect();
+    lans.sort();
+
+    assert_eq!(lans, vec!["a7a8b", "a7a8n", "a7a8q", "a7a8r"]);
+  }
+
+  #[test]
+  fn test_lan_errors() {
+    let game = Game::from_fen("4k3/P7/8/8/8/8/4p3/4K3 w - - 0 1").unwrap();
+
+    assert_eq!(Move::from_lan("a7a8", &game.state), Err("Promotion piece is missing"));
+    assert_eq!(Move::from_lan("a7a8k", &game.state), Err("Promotion piece must be one of q, r, b, n"));
+    assert_eq!(Move::from_lan("a7a8Q", &game.state), Err("Promotion piece must be one of q, r, b, n"));
+    assert_eq!(Move::from_lan("e1d1q", &game.state), Err("Only pawns reaching the last rank can promote"));
+    assert_eq!(Move::from_lan("e2e1", &game.state), Err("Square is occupied by the opponent"));
+    assert_eq!(Move::from_lan("b2b3", &game.state), Err("Square is not occupied"));
+    assert_eq!(Move::from_lan("e1e1", &game.state), Err("Start and target square are the same"));
+    assert_eq!(Move::from_lan("a7a8qq", &game.state), Err("Move must have 4 or 5 characters"));
+    assert_eq!(Move::from_lan("0000", &game.state), Err("Could not convert start to LAN"));
+    assert_eq!(Move::from_lan("a7i8", &game.state), Err("Could not convert target to LAN"));
+    assert_eq!(Move::from_lan("é7a8", &game.state), Err("Move must have 4 or 5 characters"));
+  }
+
+  #[test]
+  fn test_captures() {
+    let fens = ["r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+                "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                "8/2p5/3p4/KP5r/1R2Pp1k/8/6P1/8 b - e3 0 1"];
+
+    for fen in fens {
+      let game = Game::from_fen(fen).unwrap();
+      let board = game.state.get_relative_board();
+      let opp_mask = board.get_player_mask(game.state.get_player().other());
+
+      let mut expected: Vec<Move> = MoveGen::pseudo_legal(&game.state).into_iter()
+        .filter(|m| m.ep || m.promotion.is_some() || opp_mask & (1u64 << m.to) != 0)
+        .collect();
+      let mut captures = MoveGen::captures(&game.state);
+
+      let key = |m: &Move| (m.from, m.to, m.promotion.map(|p| p as u32));
+      expected.sort_by_key(key);
+      captures.sort_by_key(key);
+      assert_eq!(captures, expected, "{}", fen);
+    }
+  }
+
+  #[test]
+  fn test_legal_ep_discovered_check() {
+    // taking on c6 would leave the rook on h5 looking at the king
+    let game = Game::from_fen("8/8/8/KPp4r/8/8/8/4k3 w - c6 0 1").unwrap();
+    assert!(!MoveGen::legal(&game.state).iter().any(|m| m.ep));
+
+    // both pawns leave the rank, only another piece in between keeps it closed
+    let game = Game::from_fen("8/8/8/1KpP3r/8/8/8/4k3 w - c6 0 1").unwrap();
+    assert!(!MoveGen::legal(&game.state).iter().any(|m| m.ep));
+    let game = Game::from_fen("8/8/8/1KpP2Nr/8/8/8/4k3 w - c6 0 1").unwrap();
+    assert!(MoveGen::legal(&game.state).iter().any(|m| m.ep));
+
+    // the pawn giving check can be taken en passant
+    let game = Game::from_fen("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1").unwrap();
+    assert!(MoveGen::legal(&game.state).iter().any(|m| m.ep));
+  }
+
+  #[test]
+  fn test_legal_matches_filter() {
+    let fens = ["r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                "4k3/8/8/8/1b6/8/3N4/r3K2R w K - 0 1",
+                "4k3/8/5n2/8/8/8/4q3/R3K2R w KQ - 0 1"];
+    let mut rng = StdRng::seed_from_u64(25);
+
+    for fen in fens {
+      let mut game = Game::from_fen(fen).unwrap();
+
+      // a few random games from every position
+      for ply in 0..300 {
+        let legal = MoveGen::legal(&game.state);
+        let expected: Vec<Move> = MoveGen::pseudo_legal(&game.state).into_iter()
+          .filter(|m| game.state.is_legal_move(m))
+          .collect();
+        assert_eq!(legal, expected, "{}", game.state.to_fen());
+
+        if legal.is_empty() || ply % 60 == 59 {
+          game = Game::from_fen(fen).unwrap();
+          continue;
+        }
+        game.do_move(&legal[rng.random_range(0..legal.len())]);
+      }
+    }
+  }
 }
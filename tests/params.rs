@@ -0,0 +1,77 @@
+use rustchess::game::{GameState};
+use rustchess::eval::{eval, eval_with, breakdown, Term};
+use rustchess::params::{EvalParams, DEFAULT_PARAMS};
+use rustchess::board::{Player};
+
+use std::env;
+
+#[cfg(test)]
+mod params_test {
+  use super::*;
+
+  #[test]
+  pub fn test_text_round_trip() {
+    let mut params = EvalParams::default();
+    params.material[2] = (400, 350);
+    params.pst_eg[5][63] = -99;
+    params.king_attackers_scale[7] = 120;
+    params.trapped_bishop = (-7, -8);
+
+    let text = params.to_text();
+    assert!(text.lines().any(|l| l == "material 82 94 477 512 400 350 365 297 1025 936 0 0"));
+    assert_eq!(EvalParams::from_text(&text), Ok(params));
+  }
+
+  #[test]
+  pub fn test_partial_file() {
+    let params = EvalParams::from_text("# only the bishop pair\n\n  bishop_pair 1 2  \n").unwrap();
+    assert_eq!(params.bishop_pair, (1, 2));
+    assert_eq!(EvalParams {bishop_pair: DEFAULT_PARAMS.bishop_pair, ..params}, DEFAULT_PARAMS);
+  }
+
+  #[test]
+  pub fn test_errors() {
+    assert_eq!(EvalParams::from_text("bishop_pair 1"), Err("Wrong number of values for an eval parameter"));
+    assert_eq!(EvalParams::from_text("bishop_pair 1 x"), Err("Eval parameters must be integers"));
+    assert_eq!(EvalParams::from_text("queen_pair 1 2"), Err("Unknown eval parameter"));
+    assert_eq!(EvalParams::load("/nonexistent/eval.txt"), Err("Could not read the eval file"));
+  }
+
+  #[test]
+  pub fn test_save_load() {
+    let path = env::temp_dir().join(format!("rustchess-params-{}.txt", std::process::id()));
+    let path = path.to_str().unwrap();
+
+    let mut params = EvalParams::default();
+    params.mobility[2] = (9, 9);
+    params.save(path).unwrap();
+    let loaded = EvalParams::load(path);
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(loaded, Ok(params));
+  }
+
+  #[test]
+  pub fn test_custom_weights() {
+    let s = GameState::from_fen("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1").unwrap();
+    assert_eq!(eval_with(&s, &DEFAULT_PARAMS), eval(&s));
+
+    // a knight worth a queen
+    let mut params = EvalParams::default();
+    params.material[2] = (1000, 1000);
+    assert_eq!(breakdown(&s, &params).get(Term::Material, Player::White), (1000, 1000));
+    assert!(eval_with(&s, &params).0 > eval(&s).0 + 500);
+
+    // without any weights every position is equal
+    let zero = EvalParams::from_text(&DEFAULT_PARAMS.to_text().lines()
+      .filter(|l| !l.starts_with('#'))
+      .map(|l| {
+        let mut tokens = l.split_whitespace();
+        let name = tokens.next().unwrap();
+        format!("{} {}", name, tokens.map(|_| "0").collect::<Vec<_>>().join(" "))
+      })
+      .collect::<Vec<_>>().join("\n")).unwrap();
+    let s = GameState::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+    assert_eq!(eval_with(&s, &zero).0, 0);
+  }
+}
@@ -0,0 +1,76 @@
+use rustchess::game::{GameState};
+use rustchess::eval::{eval, eval_cached};
+use rustchess::pawns::{self, PawnEntry, PawnTable};
+use rustchess::board::{Player};
+use rustchess::params::{DEFAULT_PARAMS};
+
+fn structure(fen: &str) -> PawnEntry {
+  pawns::evaluate(&GameState::from_fen(fen).unwrap().get_absolute_board(), &DEFAULT_PARAMS)
+}
+
+fn white(entry: PawnEntry) -> (i32, i32) {
+  entry.score[Player::White as usize]
+}
+
+#[cfg(test)]
+mod pawns_test {
+  use super::*;
+
+  #[test]
+  pub fn test_passed() {
+    // d5 (shift 36) is passed, the doubled d4 behind it is not
+    let s = structure("4k3/8/8/3P4/3P4/8/8/4K3 w - - 0 1");
+    assert_eq!(s.passed[Player::White as usize], 1u64 << 36);
+
+    // a pawn on an adjacent file in front stops it, one that is behind does not
+    assert_eq!(structure("4k3/4p3/8/3P4/8/8/8/4K3 w - - 0 1").passed[Player::White as usize], 0);
+    assert_eq!(structure("4k3/8/8/3P4/4p3/8/8/4K3 w - - 0 1").passed[Player::White as usize], 1u64 << 36);
+    assert_eq!(structure("4k3/8/8/3P4/4p3/8/8/4K3 w - - 0 1").passed[Player::Black as usize], 1u64 << 27);
+
+    // further advanced passers are worth more, especially in the endgame
+    assert!(white(structure("4k3/8/3P4/8/8/8/8/4K3 w - - 0 1")).1 > white(structure("4k3/8/8/8/3P4/8/8/4K3 w - - 0 1")).1);
+  }
+
+  #[test]
+  pub fn test_weaknesses() {
+    // doubled and isolated against a connected pair
+    assert!(white(structure("4k3/8/8/8/8/3P4/3P4/4K3 w - - 0 1")).0 < white(structure("4k3/8/8/8/8/4P3/3P4/4K3 w - - 0 1")).0);
+    // two islands against one
+    assert!(white(structure("4k3/8/8/8/8/8/P1PP4/4K3 w - - 0 1")).1 < white(structure("4k3/8/8/8/8/8/1PPP4/4K3 w - - 0 1")).1);
+
+    // d3 can not be supported by e4 any more and c5 controls d4, with c6 it could still advance
+    let backward = white(structure("4k3/7p/8/2p5/4P3/3P4/8/4K3 w - - 0 1"));
+    let free = white(structure("4k3/7p/2p5/8/4P3/3P4/8/4K3 w - - 0 1"));
+    assert!(backward.0 < free.0);
+    assert!(backward.1 < free.1);
+  }
+
+  #[test]
+  pub fn test_free_path() {
+    // the same pawns, the passer on d6 is blocked by the king in the second position
+    let free = GameState::from_fen("6k1/8/3P4/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let blocked = GameState::from_fen("3k4/8/3P4/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let entry = pawns::evaluate(&free.get_absolute_board(), &DEFAULT_PARAMS);
+
+    assert!(pawns::free_passers(&entry, &free.get_absolute_board(), Player::White, &DEFAULT_PARAMS).1 > 0);
+    assert_eq!(pawns::free_passers(&entry, &blocked.get_absolute_board(), Player::White, &DEFAULT_PARAMS), (0, 0));
+  }
+
+  #[test]
+  pub fn test_pawn_table() {
+    let mut table = PawnTable::new();
+    let fens = ["r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 0 1",
+                "4k3/7p/8/2p5/4P3/3P4/8/4K3 w - - 0 1"];
+
+    for fen in fens {
+      let s = GameState::from_fen(fen).unwrap();
+      assert_eq!(table.probe(s.get_pawn_hash()), None);
+      assert_eq!(eval_cached(&s, &DEFAULT_PARAMS, &mut table), eval(&s));
+
+      let entry = table.probe(s.get_pawn_hash()).unwrap();
+      assert_eq!(entry, PawnEntry {key: s.get_pawn_hash(), ..pawns::evaluate(&s.get_absolute_board(), &DEFAULT_PARAMS)});
+      assert_eq!(eval_cached(&s, &DEFAULT_PARAMS, &mut table), eval(&s));
+    }
+  }
+}
@@ -0,0 +1,155 @@
+use std::io::Cursor;
+
+use rustchess::game::{Game, GameResult, WinReason, RemisReason};
+use rustchess::board::{Player};
+use rustchess::movegen::{Move};
+use rustchess::pgn::{PgnGame, PgnReader};
+
+#[cfg(test)]
+mod pgn_test {
+  use super::*;
+
+  const OPERA_GAME: &str = r#"[Event "Paris"]
+[Site "Paris FRA"]
+[Date "1858.??.??"]
+[Round "?"]
+[White "Paul Morphy"]
+[Black "Duke Karl / Count Isouard"]
+[Result "1-0"]
+[ECO "C41"]
+
+1. e4 e5 2. Nf3 d6 3. d4 Bg4 4. dxe5 Bxf3 5. Qxf3 dxe5 6. Bc4 Nf6 7. Qb3 Qe7
+8. Nc3 c6 9. Bg5 b5 10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7
+14. Rd1 Qe6 15. Bxd7+ Nxd7 16. Qb8+ Nxb8 17. Rd8# 1-0
+"#;
+
+  #[test]
+  pub fn test_export_round_trip() {
+    let mut pgn = PgnGame::from_pgn(OPERA_GAME).unwrap();
+
+    assert_eq!(pgn.get_tag("White"), Some("Paul Morphy"));
+    assert_eq!(pgn.get_tag("ECO"), Some("C41"));
+    assert_eq!(pgn.game.get_result(), GameResult::Win(Player::White, WinReason::Checkmate));
+    assert_eq!(pgn.to_pgn().unwrap(), OPERA_GAME);
+  }
+
+  #[test]
+  pub fn test_comments_nags_and_variations() {
+    let text = r#"[Event "Annotated"]
+[Result "*"]
+
+% this line is ignored
+1.e4 {best by test} e5 $1 2. Nf3!? (2. f4 exf4 (2... d5) 3. Nf3) 2... Nc6 ; rest of line
+3. Bb5 a6?! {Morphy defence {no nesting}
+4. Ba4 *
+"#;
+    let pgn = PgnGame::from_pgn(text).unwrap();
+    let expected = Game::from_fen("r1bqkbnr/1ppp1ppp/p1n5/4p3/B3P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 1 4").unwrap();
+
+    assert_eq!(pgn.game.state.get_hash(), expected.state.get_hash());
+    assert_eq!(pgn.game.state.to_fen(), expected.state.to_fen());
+  }
+
+  #[test]
+  pub fn test_setup_position() {
+    let text = r#"[Event "?"]
+[Site "?"]
+[Date "????.??.??"]
+[Round "?"]
+[White "?"]
+[Black "?"]
+[Result "1/2-1/2"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 30"]
+
+30... Kd7 31. e4 Ke6 1/2-1/2
+"#;
+    let mut pgn = PgnGame::from_pgn(text).unwrap();
+
+    assert_eq!(pgn.game.get_result(), GameResult::Remis(RemisReason::Agreement));
+    assert_eq!(pgn.to_pgn().unwrap(), text);
+  }
+
+  #[test]
+  pub fn test_export_game_in_progress() {
+    let game = Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    let mut pgn = PgnGame::new(game);
+    for lan in ["e2e4", "c7c5", "g1e2", "b8c6", "b1c3", "g8f6", "e2g3"] {
+      let m = Move::from_lan(lan, &pgn.game.state).unwrap();
+      pgn.makemove(&m).unwrap();
+    }
+    assert_eq!(pgn.get_moves().len(), 7);
+
+    pgn.set_tag("White", "Engine");
+
+    let expected = r#"[Event "?"]
+[Site "?"]
+[Date "????.??.??"]
+[Round "?"]
+[White "Engine"]
+[Black "?"]
+[Result "*"]
+
+1. e4 c5 2. Ne2 Nc6 3. Nbc3 Nf6 4. Ng3 *
+"#;
+    assert_eq!(pgn.to_pgn().unwrap(), expected);
+  }
+
+  #[test]
+  pub fn test_resignation_and_timeout() {
+    let mut resigned = PgnGame::from_pgn("1. e4 e5 2. Qh5 0-1").unwrap();
+    let mut flagged = PgnGame::from_pgn("[Termination \"time forfeit\"]\n\n1. d4 1-0").unwrap();
+
+    assert_eq!(resigned.game.get_result(), GameResult::Win(Player::Black, WinReason::Resignation));
+    assert_eq!(flagged.game.get_result(), GameResult::Win(Player::White, WinReason::Timeout));
+  }
+
+  #[test]
+  pub fn test_result_from_tag() {
+    let mut resigned = PgnGame::from_pgn("[Result \"0-1\"]\n\n1. e4 e5 2. Qh5").unwrap();
+    let mut agreed = PgnGame::from_pgn("[Result \"1/2-1/2\"]\n\n1. d4 d5").unwrap();
+
+    assert_eq!(resigned.game.get_result(), GameResult::Win(Player::Black, WinReason::Resignation));
+    assert_eq!(agreed.game.get_result(), GameResult::Remis(RemisReason::Agreement));
+  }
+
+  #[test]
+  pub fn test_reader_multiple_games() {
+    let text = format!("{}\n[Event \"Second\"]\n\n1. d4 {{[not a tag]}}\nd5 *\n\n[Event \"Third\"]\n1. c4 *\n", OPERA_GAME);
+    let games: Vec<PgnGame> = PgnReader::new(Cursor::new(text)).map(|g| g.unwrap()).collect();
+
+    assert_eq!(games.len(), 3);
+    assert_eq!(games[0].get_tag("Event"), Some("Paris"));
+    assert_eq!(games[1].get_tag("Event"), Some("Second"));
+    assert_eq!(games[1].game.get_history().get_states().len(), 3);
+    assert_eq!(games[2].get_tag("Event"), Some("Third"));
+  }
+
+  #[test]
+  pub fn test_reader_games_without_tags() {
+    let text = "1. e4 e5 1-0\n1. d4 {comment with 0-1} d5 (2. c4 *) 0-1 1. c4 *\n% 1-0\n1. Nf3 1/2-1/2";
+    let games: Vec<PgnGame> = PgnReader::new(Cursor::new(text)).map(|g| g.unwrap()).collect();
+
+    assert_eq!(games.len(), 4);
+    assert_eq!(games[0].get_moves().len(), 2);
+    assert_eq!(games[1].get_moves().len(), 2);
+    assert_eq!(games[2].get_moves().len(), 1);
+    assert_eq!(games[3].get_moves().len(), 1);
+  }
+
+  #[test]
+  pub fn test_export_needs_recorded_moves() {
+    let mut pgn = PgnGame::from_pgn("1. e4 e5 *").unwrap();
+    let m = Move::from_lan("g1f3", &pgn.game.state).unwrap();
+    pgn.game.makemove(&m).unwrap();
+
+    assert!(pgn.to_pgn().is_err());
+  }
+
+  #[test]
+  pub fn test_illegal_movetext() {
+    assert!(PgnGame::from_pgn("1. e4 e5 2. Ke3 *").is_err());
+    assert!(PgnGame::from_pgn("1. e4 (1. d4 *").is_err());
+    assert!(PgnGame::from_pgn("1. e4 {unterminated *").is_err());
+  }
+}
@@ -0,0 +1,29 @@
+use rustchess::score::{Score};
+
+#[cfg(test)]
+mod score_test {
+  use super::*;
+
+  #[test]
+  pub fn test_mate_encoding() {
+    assert_eq!(Score::mate_in(1).mate_moves(), Some(1));
+    assert_eq!(Score::mate_in(3).mate_moves(), Some(2));
+    assert_eq!(Score::mated_in(2).mate_moves(), Some(-1));
+    assert_eq!(Score::mated_in(0).mate_moves(), Some(0));
+    assert_eq!(Score::cp(350).mate_moves(), None);
+    assert_eq!(-Score::mate_in(4), Score::mated_in(4));
+
+    // shorter mates are better, longer defences are better for the mated side
+    assert!(Score::mate_in(1) > Score::mate_in(3));
+    assert!(Score::mated_in(2) < Score::mated_in(4));
+    assert!(Score::mate_in(99) > Score::cp(9000));
+    assert!(Score::INFINITY > Score::mate_in(0));
+  }
+
+  #[test]
+  pub fn test_uci_format() {
+    assert_eq!(Score::cp(-25).to_string(), "cp -25");
+    assert_eq!(Score::mate_in(5).to_string(), "mate 3");
+    assert_eq!(Score::mated_in(4).to_string(), "mate -2");
+  }
+}
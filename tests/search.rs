@@ -1,27 +1,44 @@
 use rustchess::game::{Game};
-use rustchess::search::{eval};
+use rustchess::eval::{eval};
+use rustchess::search::{self, SearchLimits, Searcher};
 use rustchess::board::{Player};
 use rustchess::movegen::{Move};
 
-use rustchess::search;
+use rustchess::score::{Score};
+use rustchess::tt::{TranspositionTable};
+use rustchess::pawns::{PawnTable};
 
-fn minimax_eval(game: &mut Game, depth: u32)  -> f64 {
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration};
+
+// from white's point of view like the values of the root search
+fn mated_value(game: &Game, ply: u32) -> Score {
+  match game.state.get_player() {
+    Player::White => Score::mated_in(ply),
+    Player::Black => Score::mate_in(ply),
+  }
+}
+
+fn minimax_eval(game: &mut Game, depth: u32, ply: u32) -> Score {
   if depth == 0 {
-    return eval(&mut game.state);
+    return eval(&game.state);
   }
 
   let moves = game.legal_moves();
+  if moves.is_empty() {
+    return if game.state.is_check(game.state.get_player()) { mated_value(game, ply) } else { Score::ZERO };
+  }
 
-  
   let mut best_v = match game.state.get_player() {
-    Player::White => -std::f64::INFINITY,
-    Player::Black => std::f64::INFINITY,
+    Player::White => -Score::INFINITY,
+    Player::Black => Score::INFINITY,
   };
 
   if game.state.get_player() == Player::White {
     for m in moves.iter() {
       game.makemove(m).unwrap();
-      let v = minimax_eval(game, depth-1);
+      let v = minimax_eval(game, depth-1, ply+1);
       game.undo_move();
 
       if v > best_v {
@@ -31,7 +48,7 @@ fn minimax_eval(game: &mut Game, depth: u32)  -> f64 {
   } else {
     for m in moves.iter() {
       game.makemove(m).unwrap();
-      let v = minimax_eval(game, depth-1);
+      let v = minimax_eval(game, depth-1, ply+1);
       game.undo_move();
 
       if v < best_v {
@@ -44,19 +61,19 @@ fn minimax_eval(game: &mut Game, depth: u32)  -> f64 {
 }
 
 
-fn root_search(game: &mut Game, depth: u32) -> (Option<Move>, f64) {
+fn root_search(game: &mut Game, depth: u32) -> (Option<Move>, Score) {
   let moves = game.legal_moves();
   
   let mut best_move = None;
   let mut best_v = match game.state.get_player() {
-    Player::White => -std::f64::INFINITY,
-    Player::Black => std::f64::INFINITY,
+    Player::White => -Score::INFINITY,
+    Player::Black => Score::INFINITY,
   };
 
   if game.state.get_player() == Player::White {
     for m in moves.iter() {
       game.makemove(m).unwrap();
-      let v = minimax_eval(game, depth);
+      let v = minimax_eval(game, depth, 1);
       game.undo_move();
 
       println!("Move: {} Value: {}", Move::to_lan(m, &game.state).unwrap(), v);
@@ -69,7 +86,7 @@ fn root_search(game: &mut Game, depth: u32) -> (Option<Move>, f64) {
   } else {
     for m in moves.iter() {
       game.makemove(m).unwrap();
-      let v = minimax_eval(game, depth);
+      let v = minimax_eval(game, depth, 1);
       game.undo_move();
 
       if v < best_v {
@@ -83,6 +100,32 @@ fn root_search(game: &mut Game, depth: u32) -> (Option<Move>, f64) {
   (best_move, best_v)
 }
 
+// the engine with the static eval at the leaves, the same tree the minimax above searches
+fn full_width_search(game: &mut Game, depth: u32) -> (Vec<Move>, Score) {
+  let stop = Arc::new(AtomicBool::new(false));
+  let ponder = Arc::new(AtomicBool::new(false));
+  let mut tt = TranspositionTable::new(1);
+  let mut pawn_table = PawnTable::new();
+
+  let mut searcher = Searcher::new(SearchLimits::default(), &mut tt, &mut pawn_table, stop, ponder);
+  searcher.set_quiescence(false);
+  searcher.search_root(game, depth)
+}
+
+// the value of the quiescence search for the side to move
+fn quiescence(game: &mut Game) -> Score {
+  let stop = Arc::new(AtomicBool::new(false));
+  let ponder = Arc::new(AtomicBool::new(false));
+  let mut tt = TranspositionTable::new(1);
+  let mut pawn_table = PawnTable::new();
+  let color = match game.state.get_player() {
+    Player::White => 1,
+    Player::Black => -1,
+  };
+
+  Searcher::new(SearchLimits::default(), &mut tt, &mut pawn_table, stop, ponder).quiescence(game, 0, -Score::INFINITY, Score::INFINITY, color)
+}
+
 #[cfg(test)]
 mod test_search {
   use super::*;
@@ -91,10 +134,10 @@ mod test_search {
   pub fn test_fen_1() {
     let mut game = Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
     let (t_m, v_m) = root_search(&mut game, 3);
-    let (t_s, v_s) = search::root_search(&mut game, 3);
+    let (pv_s, v_s) = full_width_search(&mut game, 3);
 
 
-    assert_eq!(t_m, t_s, "Moves not the same");
+    assert_eq!(t_m, pv_s.first().copied(), "Moves not the same");
     assert_eq!(v_m, v_s, "Value returened not the same");
   }
 
@@ -102,10 +145,134 @@ mod test_search {
   pub fn test_fen_2() {
     let mut game = Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
     let (t_m, v_m) = root_search(&mut game, 2);
-    let (t_s, v_s) = search::root_search(&mut game, 2);
+    let (pv_s, v_s) = full_width_search(&mut game, 2);
 
 
-    assert_eq!(t_m, t_s, "Failed at depth {}: Moves not the same", 3);
+    assert_eq!(t_m, pv_s.first().copied(), "Failed at depth {}: Moves not the same", 3);
     assert_eq!(v_m, v_s, "Failed at depth {}: Value returened not the same", 3);
   }
+
+  #[test]
+  pub fn test_quiescence() {
+    // taking the defended pawn loses the queen, standing pat is better
+    let mut game = Game::from_fen("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1").unwrap();
+    assert_eq!(quiescence(&mut game), eval(&game.state));
+
+    // a hanging queen is taken
+    let mut game = Game::from_fen("4k3/8/8/3q4/8/8/8/3QK3 w - - 0 1").unwrap();
+    assert!(quiescence(&mut game) > eval(&game.state));
+
+    // in check the static eval does not count, the king has to step aside and the queen is lost
+    let mut game = Game::from_fen("4k3/8/8/8/r3K2Q/8/8/8 w - - 0 1").unwrap();
+    assert!(eval(&game.state) > Score::ZERO);
+    assert!(quiescence(&mut game) < Score::ZERO);
+
+    let mut game = Game::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+    assert_eq!(quiescence(&mut game), Score::mated_in(0));
+  }
+
+  #[test]
+  pub fn test_move_ordering() {
+    let mut game = Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+    let mut tt = TranspositionTable::new(1);
+    let mut pawn_table = PawnTable::new();
+    let stop = Arc::new(AtomicBool::new(false));
+    let ponder = Arc::new(AtomicBool::new(false));
+
+    let mut searcher = Searcher::new(SearchLimits::depth(2), &mut tt, &mut pawn_table, stop.clone(), ponder.clone());
+    let (_, v) = searcher.go(&mut game, |_| ());
+    let ordered = searcher.get_nodes();
+
+    let mut tt = TranspositionTable::new(1);
+    let mut pawn_table = PawnTable::new();
+    let mut searcher = Searcher::new(SearchLimits::depth(2), &mut tt, &mut pawn_table, stop, ponder);
+    searcher.set_ordering(false);
+    let (_, v_u) = searcher.go(&mut game, |_| ());
+    let unordered = searcher.get_nodes();
+
+    // ordering only makes the tree smaller, it must not change the value of the search
+    assert!(ordered < unordered, "{} >= {}", ordered, unordered);
+    assert_eq!(v, v_u);
+  }
+
+  #[test]
+  pub fn test_shortest_mate() {
+    // Ra8 mates at once, a deeper search also sees slower mates
+    let mut game = Game::from_fen("7k/8/6K1/8/8/8/8/R7 w - - 0 1").unwrap();
+    let (pv, v) = search::root_search(&mut game, 2);
+
+    assert_eq!(pv.len(), 1);
+    assert_eq!(Move::to_lan(&pv[0], &game.state).unwrap(), "a1a8");
+    assert_eq!(v, Score::mate_in(1));
+
+    let mut game = Game::from_fen("r6k/8/8/8/8/8/5PPP/6K1 b - - 0 1").unwrap();
+    let (pv, v) = search::root_search(&mut game, 2);
+
+    assert_eq!(pv.len(), 1);
+    assert_eq!(Move::to_lan(&pv[0], &game.state).unwrap(), "a8a1");
+    assert_eq!(v, -Score::mate_in(1));
+  }
+
+  #[test]
+  pub fn test_ponderhit_keeps_start() {
+    let mut game = Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+    let mut tt = TranspositionTable::new(1);
+    let mut pawn_table = PawnTable::new();
+    let stop = Arc::new(AtomicBool::new(false));
+    let ponder = Arc::new(AtomicBool::new(true));
+
+    let hit = ponder.clone();
+    let gui = std::thread::spawn(move || {
+      std::thread::sleep(Duration::from_millis(100));
+      hit.store(false, Ordering::Relaxed);
+    });
+
+    // the budget runs from the ponderhit, the reported time from the go. with 50ms the last iteration
+    // may end as soon as half of it is used
+    let limits = SearchLimits {ponder: true, movetime: Some(50), ..Default::default()};
+    let mut searcher = Searcher::new(limits, &mut tt, &mut pawn_table, stop, ponder);
+    let mut last = Duration::ZERO;
+    searcher.go(&mut game, |info| last = info.time);
+    gui.join().unwrap();
+
+    assert!(searcher.elapsed() >= Duration::from_millis(125), "{:?}", searcher.elapsed());
+    assert!(last <= searcher.elapsed());
+  }
+
+  #[test]
+  pub fn test_game_over() {
+    // values are from white's point of view
+    for (fen, value) in [("6k1/8/8/8/8/8/5PPP/r5K1 w - - 0 1", Score::mated_in(0)),
+                         ("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", -Score::mated_in(0)),
+                         ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", Score::ZERO)] {
+      let mut game = Game::from_fen(fen).unwrap();
+      let mut tt = TranspositionTable::new(1);
+      let mut pawn_table = PawnTable::new();
+      let stop = Arc::new(AtomicBool::new(false));
+      let ponder = Arc::new(AtomicBool::new(false));
+
+      let mut reports = 0;
+      let (pv, v) = Searcher::new(SearchLimits::depth(3), &mut tt, &mut pawn_table, stop, ponder).go(&mut game, |_| reports += 1);
+      assert!(pv.is_empty());
+      assert_eq!(v, value, "{}", fen);
+      assert_eq!(reports, 0);
+    }
+  }
+
+  #[test]
+  pub fn test_principal_variation() {
+    // white wins the rook with a fork, the pv has to show the whole line
+    let mut game = Game::from_fen("r3k3/7p/8/3N4/8/8/7P/4K3 w - - 0 1").unwrap();
+    let (pv, v) = search::root_search(&mut game, 2);
+
+    let lan: Vec<String> = pv.iter().map(|m| {
+      let s = Move::to_lan(m, &game.state).unwrap();
+      game.makemove(m).unwrap();
+      s
+    }).collect();
+
+    assert_eq!(lan[0], "d5c7");
+    assert!(lan.len() >= 3, "{:?}", lan);
+    assert_eq!(v, eval(&game.state));
+  }
 }
@@ -0,0 +1,78 @@
+use rustchess::tt::{Bound, TranspositionTable};
+use rustchess::score::{Score};
+use rustchess::movegen::{Move};
+use rustchess::board::{Pieces};
+
+#[cfg(test)]
+mod tt_test {
+  use super::*;
+
+  fn some_move() -> Move {
+    Move {piece: Pieces::Knight, from: 1, to: 18, promotion: None, ep: false}
+  }
+
+  #[test]
+  pub fn test_store_probe() {
+    let mut tt = TranspositionTable::new(1);
+    tt.store(0xdead_beef, 3, Bound::Lower, Score::cp(42), Some(some_move()), 2);
+
+    let e = tt.probe(0xdead_beef, 2).unwrap();
+    assert_eq!(e.depth, 3);
+    assert_eq!(e.bound, Bound::Lower);
+    assert_eq!(e.value, Score::cp(42));
+    assert_eq!(e.best_move, Some(some_move()));
+
+    // same slot, different position
+    assert!(tt.probe(0xdead_beef + ((tt.capacity() as u64) << 4), 2).is_none());
+
+    tt.clear();
+    assert!(tt.probe(0xdead_beef, 2).is_none());
+  }
+
+  #[test]
+  pub fn test_mate_values_are_ply_adjusted() {
+    let mut tt = TranspositionTable::new(1);
+    // a mate found 3 plies from the root is one ply further away when reached at ply 4
+    tt.store(7, 2, Bound::Exact, Score::mate_in(5), None, 3);
+    assert_eq!(tt.probe(7, 4).unwrap().value, Score::mate_in(6));
+
+    tt.store(8, 2, Bound::Exact, Score::mated_in(5), None, 3);
+    assert_eq!(tt.probe(8, 1).unwrap().value, Score::mated_in(3));
+
+    tt.store(9, 2, Bound::Exact, Score::cp(250), None, 3);
+    assert_eq!(tt.probe(9, 10).unwrap().value, Score::cp(250));
+  }
+
+  #[test]
+  pub fn test_replacement() {
+    let mut tt = TranspositionTable::new(1);
+    let other = 5 + tt.capacity() as u64;
+
+    // a shallow result of the same search does not evict a deeper one
+    tt.store(5, 6, Bound::Exact, Score::cp(1), Some(some_move()), 0);
+    tt.store(other, 2, Bound::Exact, Score::cp(2), None, 0);
+    assert!(tt.probe(5, 0).is_some());
+    assert!(tt.probe(other, 0).is_none());
+
+    // the same position keeps its best move if none is given
+    tt.store(5, 1, Bound::Upper, Score::ZERO, None, 0);
+    assert_eq!(tt.probe(5, 0).unwrap().best_move, Some(some_move()));
+
+    // entries of an old search are replaced
+    tt.store(5, 6, Bound::Exact, Score::cp(1), None, 0);
+    tt.new_search();
+    tt.store(other, 1, Bound::Exact, Score::cp(2), None, 0);
+    assert!(tt.probe(5, 0).is_none());
+    assert_eq!(tt.probe(other, 0).unwrap().value, Score::cp(2));
+  }
+
+  #[test]
+  pub fn test_resize() {
+    let mut tt = TranspositionTable::new(1);
+    let small = tt.capacity();
+    assert!(small.is_power_of_two());
+
+    tt.resize(4);
+    assert_eq!(tt.capacity(), 4 * small);
+  }
+}
@@ -0,0 +1,70 @@
+use rustchess::game::{GameState};
+use rustchess::eval::{eval};
+use rustchess::params::{EvalParams, DEFAULT_PARAMS};
+use rustchess::tune::{self, TuneEntry, TuneOptions};
+
+const LINES: [&str; 6] = [
+  "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - c9 \"1/2-1/2\";",
+  "4k3/8/8/8/8/8/8/1N2KQ2 w - - 0 1 [1.0]",
+  "4k3/8/8/8/8/8/PPP5/4K3 w - - 1-0",
+  "rn2k3/8/8/8/8/8/8/4K3 w - - hmvc 0; c9 \"0-1\";",
+  "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 0.5",
+  "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 3 17 [0.0]",
+];
+
+#[cfg(test)]
+mod tune_test {
+  use super::*;
+
+  #[test]
+  pub fn test_parse_entry() {
+    let entries: Vec<TuneEntry> = LINES.iter().map(|l| tune::parse_entry(l).unwrap()).collect();
+    let results: Vec<f64> = entries.iter().map(|e| e.result).collect();
+
+    assert_eq!(results, vec![0.5, 1.0, 1.0, 0.0, 0.5, 0.0]);
+    assert_eq!(entries[3].state.to_fen(), "rn2k3/8/8/8/8/8/8/4K3 w - - 0 1");
+    assert_eq!(entries[5].state.to_fen(), "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 3 17");
+
+    assert!(tune::parse_entry("4k3/8/8/8/8/8/8/4K3 w - -").is_err());
+    assert!(tune::parse_entry("4k3/8/8/8/8/8/8/4K3 w - - 0 1").is_err());
+    assert!(tune::parse_entry("4k3/8/8/8/8/8/8/4K3 w - - c9 \"*\";").is_err());
+    assert!(tune::parse_entry("4k3/8/8/8/8/8/8/4K3 w - - [2.0]").is_err());
+  }
+
+  #[test]
+  pub fn test_error() {
+    assert_eq!(tune::sigmoid(0.0, 1.3), 0.5);
+    assert!((tune::sigmoid(150.0, 1.3) + tune::sigmoid(-150.0, 1.3) - 1.0).abs() < 1e-12);
+
+    let entries: Vec<TuneEntry> = LINES.iter().map(|l| tune::parse_entry(l).unwrap()).collect();
+    let single = tune::error(&entries, &DEFAULT_PARAMS, 1.0, 1);
+    assert!(single > 0.0);
+    assert!((tune::error(&entries, &DEFAULT_PARAMS, 1.0, 4) - single).abs() < 1e-12);
+  }
+
+  #[test]
+  pub fn test_fit_k() {
+    // results that follow the sigmoid exactly
+    let entries: Vec<TuneEntry> = LINES.iter().map(|l| {
+      let state = GameState::from_fen(&tune::parse_entry(l).unwrap().state.to_fen()).unwrap();
+      TuneEntry {state, result: tune::sigmoid(eval(&state).0 as f64, 0.73)}
+    }).collect();
+
+    assert!((tune::fit_k(&entries, &DEFAULT_PARAMS, 2) - 0.73).abs() < 0.01);
+  }
+
+  #[test]
+  pub fn test_tune() {
+    let entries: Vec<TuneEntry> = LINES.iter().map(|l| tune::parse_entry(l).unwrap()).collect();
+    let options = TuneOptions {threads: 2, iterations: 1, step: 1};
+    let mut passes = vec![];
+
+    let tuned = tune::tune(&entries, &DEFAULT_PARAMS, 1.0, &options, |pass, error, _| passes.push((pass, error)));
+
+    assert_eq!(passes.len(), 1);
+    assert!(passes[0].1 < tune::error(&entries, &DEFAULT_PARAMS, 1.0, 1));
+    assert!((passes[0].1 - tune::error(&entries, &tuned, 1.0, 1)).abs() < 1e-12);
+    assert!(tuned != EvalParams::default());
+    assert_eq!(tuned.values().len(), DEFAULT_PARAMS.values().len());
+  }
+}
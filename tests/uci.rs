@@ -0,0 +1,281 @@
+use std::io::{self, Cursor, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rustchess::uci::{Uci};
+use rustchess::movegen::{Move};
+use rustchess::game::{Game, GameState};
+use rustchess::eval::{breakdown};
+use rustchess::params::{DEFAULT_PARAMS};
+
+#[cfg(test)]
+mod uci_test {
+  use super::*;
+
+  // output buffer that stays readable while the engine owns a handle to it
+  #[derive(Clone, Default)]
+  struct Output(Arc<Mutex<Vec<u8>>>);
+
+  impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+    }
+  }
+
+  impl Output {
+    fn lines(&self) -> Vec<String> {
+      String::from_utf8(self.0.lock().unwrap().clone()).unwrap().lines().map(String::from).collect()
+    }
+  }
+
+  fn run(input: &str) -> Vec<String> {
+    let out = Output::default();
+    Uci::new(out.clone()).run(Cursor::new(input)).unwrap();
+
+    out.lines()
+  }
+
+  // output without the info lines of the search
+  fn run_quiet(input: &str) -> Vec<String> {
+    run(input).into_iter().filter(|l| !l.starts_with("info ")).collect()
+  }
+
+  #[test]
+  pub fn test_handshake() {
+    let out = run("uci\nisready\n");
+
+    assert_eq!(out, vec!["id name rustchess", "id author scriptus_longus",
+                         "option name Hash type spin default 16 min 1 max 1024",
+                         "option name EvalFile type string default <empty>", "uciok", "readyok"]);
+  }
+
+  #[test]
+  pub fn test_quit_stops_reading() {
+    let out = run("isready\nquit\nisready\n");
+
+    assert_eq!(out, vec!["readyok"]);
+  }
+
+  #[test]
+  pub fn test_unknown_tokens_are_skipped() {
+    let out = run("joho isready\nfoo bar\n\n");
+
+    assert_eq!(out, vec!["readyok"]);
+  }
+
+  #[test]
+  pub fn test_position_moves() {
+    let mut engine = Uci::new(Output::default());
+
+    engine.handle("position startpos moves e2e4 e7e5 g1f3").unwrap();
+    assert_eq!(engine.game.state.to_fen(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+
+    engine.handle("position fen 4k3/P7/8/8/8/8/8/4K3 w - - moves a7a8q").unwrap();
+    assert_eq!(engine.game.state.to_fen(), "Q3k3/8/8/8/8/8/8/4K3 b - - 0 1");
+
+    engine.handle("position startpos").unwrap();
+    assert_eq!(engine.game.state.to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+  }
+
+  #[test]
+  pub fn test_position_errors() {
+    let out = run("position startpos moves e2e5\nposition fen 8/8 w\nposition somewhere\n");
+
+    assert_eq!(out, vec!["info string Error: Illegal move in position command",
+                         "info string Error: FEN must have 4 or 6 fields",
+                         "info string Error: Position must be startpos or fen"]);
+  }
+
+  #[test]
+  pub fn test_position_error_keeps_position() {
+    let mut engine = Uci::new(Output::default());
+
+    engine.handle("position startpos moves e2e4").unwrap();
+    engine.handle("position startpos moves d2d4 d7d5 e1e3").unwrap();
+    assert_eq!(engine.game.state.to_fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    assert_eq!(engine.game.get_history().get_states().len(), 2);
+  }
+
+  #[test]
+  pub fn test_go_perft() {
+    assert_eq!(run("go perft 2
+"), vec!["Nodes searched: 400"]);
+
+    let out = run("debug on
+position fen 4k3/8/8/8/8/8/8/4K2R w K - 0 1
+go perft 1
+");
+    assert_eq!(out.len(), 17);
+    assert!(out.contains(&String::from("e1g1: 1")));
+    assert_eq!(out[15], "");
+    assert_eq!(out[16], "Nodes searched: 15");
+  }
+
+  #[test]
+  pub fn test_bad_fen_does_not_crash() {
+    let out = run("position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1\nposition fen 8/8/8/8/8/8/8/8 w - - 0 1\ngo depth 1\n");
+
+    // the engine keeps the start position and still answers
+    assert!(out[0].starts_with("info string Error: "));
+    assert!(out[1].starts_with("info string Error: "));
+    assert!(out.last().unwrap().starts_with("bestmove ") && out.last().unwrap() != "bestmove 0000");
+  }
+
+  #[test]
+  pub fn test_go_returns_legal_move() {
+    let out = run_quiet("position startpos moves e2e4\ngo\n");
+    let bestmove = out.last().unwrap().split_whitespace().nth(1).unwrap();
+
+    let mut game = Game::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+    let m = Move::from_lan(bestmove, &game.state).unwrap();
+    assert!(game.legal_moves().contains(&m));
+  }
+
+  #[test]
+  pub fn test_go_searchmoves() {
+    let out = run_quiet("position startpos\ngo depth 2 searchmoves a2a3\ngo searchmoves b1c3 depth 1\n");
+
+    let moves: Vec<&str> = out.iter().map(|l| l.split_whitespace().nth(1).unwrap()).collect();
+    assert_eq!(moves, vec!["a2a3", "b1c3"]);
+  }
+
+  #[test]
+  pub fn test_go_limits() {
+    let out = run_quiet("position startpos\ngo nodes 1000\ngo movetime 50\ngo wtime 100 btime 100 winc 0 binc 0 movestogo 1\ngo wtime -20 btime 100\n");
+
+    assert_eq!(out.len(), 4);
+    assert!(out.iter().all(|x| x.starts_with("bestmove ") && x != "bestmove 0000"));
+  }
+
+  #[test]
+  pub fn test_go_errors() {
+    let out = run("go depth x\ngo sometimes\ngo searchmoves e2e5\n");
+
+    assert_eq!(out, vec!["info string Error: go parameter expects a number",
+                         "info string Error: Unknown go parameter",
+                         "info string Error: Illegal move in searchmoves"]);
+  }
+
+  #[test]
+  pub fn test_stop_infinite() {
+    let out = run_quiet("position startpos\ngo infinite\nisready\nstop\nisready\n");
+
+    assert_eq!(out.len(), 3);
+    assert_eq!(out[0], "readyok");
+    assert!(out[1].starts_with("bestmove ") && out[1] != "bestmove 0000");
+    assert_eq!(out[2], "readyok");
+  }
+
+  #[test]
+  pub fn test_position_stops_search() {
+    // a new position does not wait for the whole minute of the running search
+    let start = Instant::now();
+    let out = run_quiet("position startpos\ngo movetime 60000\nposition startpos moves e2e4\nisready\n");
+
+    assert!(start.elapsed() < Duration::from_secs(30));
+    assert_eq!(out.len(), 2);
+    assert!(out[0].starts_with("bestmove ") && out[0] != "bestmove 0000");
+    assert_eq!(out[1], "readyok");
+  }
+
+  #[test]
+  pub fn test_ponder() {
+    // the clock only starts on ponderhit, the budget is a few milliseconds then
+    let out = run_quiet("position startpos moves e2e4\ngo ponder wtime 300 btime 300\nisready\nponderhit\n");
+    assert_eq!(out.len(), 2);
+    assert_eq!(out[0], "readyok");
+    assert!(out[1].starts_with("bestmove "));
+
+    let out = run_quiet("position startpos\ngo ponder wtime 300 btime 300\nstop\n");
+    assert_eq!(out.len(), 1);
+    assert!(out[0].starts_with("bestmove "));
+  }
+
+  #[test]
+  pub fn test_quit_while_searching() {
+    let out = Output::default();
+    let mut engine = Uci::new(out.clone());
+
+    // a fast machine may finish the first iterations before the quit arrives
+    engine.handle("go infinite").unwrap();
+    assert!(!engine.handle("quit").unwrap());
+    assert!(out.lines().last().unwrap().starts_with("bestmove "));
+    assert!(out.lines().iter().all(|l| l.starts_with("info ") || l.starts_with("bestmove ")));
+  }
+
+  #[test]
+  pub fn test_go_info() {
+    let out = run("position startpos\ngo depth 3\n");
+
+    assert_eq!(out.len(), 4);
+    for (i, line) in out[..3].iter().enumerate() {
+      let tokens: Vec<&str> = line.split_whitespace().collect();
+      assert_eq!(tokens[..3], ["info", "depth", &(i + 1).to_string()]);
+      for name in ["seldepth", "score", "nodes", "nps", "time", "pv"] {
+        assert!(tokens.contains(&name), "{} missing in {}", name, line);
+      }
+    }
+
+    // the best move and the ponder move are the first two moves of the last pv
+    let pv: Vec<&str> = out[2].split(" pv ").nth(1).unwrap().split_whitespace().collect();
+    assert!(pv.len() >= 2);
+    assert_eq!(out[3], format!("bestmove {} ponder {}", pv[0], pv[1]));
+  }
+
+  #[test]
+  pub fn test_go_mate() {
+    let out = run("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo mate 1\n");
+    assert!(out[0].contains(" score mate 1 "));
+    assert_eq!(out.last().unwrap(), "bestmove a1a8");
+
+    let out = run("position fen r5k1/5ppp/8/8/8/8/5PPP/6K1 b - - 0 1\ngo depth 3\n");
+    assert!(out[0].contains(" score mate 1 "));
+    assert_eq!(out.last().unwrap(), "bestmove a8a1");
+  }
+
+  #[test]
+  pub fn test_go_game_over() {
+    assert_eq!(run("position fen 7k/5Q2/6K1/8/8/8/8/8 b - - 0 1\ngo depth 3\n"), vec!["bestmove 0000"]);
+    assert_eq!(run("position fen R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1\ngo depth 3\n"), vec!["bestmove 0000"]);
+  }
+
+  #[test]
+  pub fn test_setoption() {
+    let out = run("setoption name Hash value 2\nsetoption name hash value 4096\nsetoption name Foo value 1\nsetoption\n");
+
+    assert_eq!(out, vec!["info string Error: Hash must be a size in MB between 1 and 1024",
+                         "info string Error: Unknown option",
+                         "info string Error: setoption expects a name"]);
+  }
+
+  #[test]
+  pub fn test_eval() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let out = run(&format!("position fen {}\neval\n", fen));
+
+    let expected = breakdown(&GameState::from_fen(fen).unwrap(), &DEFAULT_PARAMS).to_string();
+    assert_eq!(out, expected.lines().collect::<Vec<_>>());
+    assert!(out.iter().any(|l| l.trim_start().starts_with("king safety")));
+  }
+
+  #[test]
+  pub fn test_eval_file() {
+    let path = std::env::temp_dir().join(format!("rustchess-uci-eval-{}.txt", std::process::id()));
+    let path = path.to_str().unwrap();
+    std::fs::write(path, "material 100 100 500 500 300 300 300 300 900 900 0 0\n").unwrap();
+
+    let out = run(&format!("position fen 4k3/8/8/8/8/8/8/1N2K3 w - - 0 1\nsetoption name EvalFile value {}\neval\n\
+                            setoption name EvalFile value <empty>\neval\nsetoption name EvalFile value /nonexistent\n", path));
+    std::fs::remove_file(path).unwrap();
+
+    let material: Vec<&String> = out.iter().filter(|l| l.trim_start().starts_with("material")).collect();
+    assert_eq!(material.len(), 2);
+    assert!(material[0].contains(" 300    300 "), "{}", material[0]);
+    assert!(!material[1].contains(" 300    300 "), "{}", material[1]);
+    assert_eq!(out.last().unwrap(), "info string Error: Could not read the eval file");
+  }
+}
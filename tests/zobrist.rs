@@ -0,0 +1,85 @@
+use rustchess::game::{Game, GameState};
+use rustchess::movegen::{Move};
+
+#[cfg(test)]
+mod zobrist_test {
+  use super::*;
+
+  fn play(game: &mut Game, moves: &[&str]) {
+    for lan in moves {
+      let m = Move::from_lan(lan, &game.state).unwrap();
+      game.makemove(&m).unwrap();
+    }
+  }
+
+  #[test]
+  pub fn test_transposition_same_hash() {
+    let mut a = Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    let mut b = Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+
+    play(&mut a, &["g1f3", "g8f6", "b1c3", "b8c6"]);
+    play(&mut b, &["b1c3", "b8c6", "g1f3", "g8f6"]);
+
+    assert_eq!(a.state.get_hash(), b.state.get_hash());
+  }
+
+  #[test]
+  pub fn test_hash_matches_fen() {
+    let mut game = Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+    play(&mut game, &["e1g1", "a8b8", "a2a4", "b4a3"]);
+
+    let from_fen = GameState::from_fen(&game.state.to_fen()).unwrap();
+    assert_eq!(game.state.get_hash(), from_fen.get_hash());
+  }
+
+  #[test]
+  pub fn test_hash_distinguishes_state() {
+    let white = GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    let black = GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap();
+    let no_castle = GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1").unwrap();
+    let ep = GameState::from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2").unwrap();
+    let no_ep = GameState::from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2").unwrap();
+
+    assert_ne!(white.get_hash(), black.get_hash());
+    assert_ne!(white.get_hash(), no_castle.get_hash());
+    assert_ne!(ep.get_hash(), no_ep.get_hash());
+  }
+
+  #[test]
+  pub fn test_double_push_hash() {
+    // nothing can take on e3, the position is the same as if the pawn had come from e3. the square
+    // is kept for the fen
+    let mut game = Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    play(&mut game, &["e2e4"]);
+    assert!(game.state.get_ep().is_some());
+    assert_eq!(game.state.get_hash(), GameState::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap().get_hash());
+
+    // a pawn next to it keeps the square
+    let mut game = Game::from_fen("rnbqkbnr/ppp1pppp/8/8/3p4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    play(&mut game, &["e2e4"]);
+    assert!(game.state.get_ep().is_some());
+    assert_ne!(game.state.get_hash(), GameState::from_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap().get_hash());
+
+    // unless taking would leave the king in check
+    let mut game = Game::from_fen("8/8/8/8/k2p3R/8/4P3/4K3 w - - 0 1").unwrap();
+    play(&mut game, &["e2e4"]);
+    assert_eq!(game.state.get_hash(), GameState::from_fen("8/8/8/8/k2pP2R/8/8/4K3 b - - 0 1").unwrap().get_hash());
+  }
+
+  #[test]
+  pub fn test_pawn_hash() {
+    let mut game = Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    let start = game.state.get_pawn_hash();
+
+    // only pawns change the pawn hash
+    play(&mut game, &["g1f3", "g8f6"]);
+    assert_eq!(game.state.get_pawn_hash(), start);
+    assert_ne!(game.state.get_hash(), GameState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().get_hash());
+
+    // captures, en passant and promotions keep it in sync with the position
+    play(&mut game, &["e2e4", "d7d5", "e4e5", "f6d7", "d2d4", "c7c5", "d4c5", "d5d4", "c2c4", "d4c3", "c5c6", "c3b2", "c6b7", "b2a1q", "b7c8q"]);
+    assert_ne!(game.state.get_pawn_hash(), start);
+    assert_eq!(game.state.get_pawn_hash(), game.state.compute_pawn_hash());
+    assert_eq!(game.state.get_pawn_hash(), GameState::from_fen(&game.state.to_fen()).unwrap().get_pawn_hash());
+  }
+}